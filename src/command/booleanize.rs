@@ -0,0 +1,65 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::EmptyLomakResult;
+use crate::model::modifier::booleanize::{BooleanEncoding, Booleanizer};
+use crate::model::SharedModel;
+use crate::variables::GroupedVariables;
+
+static NAME: &str = "booleanize";
+static ABOUT: &str = "Replace multivalued components with a Boolean component for each threshold";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Rules of the threshold components: vanham or ordered
+    #[structopt(short, long, default_value = "vanham")]
+    encoding: BooleanEncoding,
+
+    /// Store the Boolean model under a new name instead of replacing the current model
+    #[structopt(long = "as")]
+    name: Option<String>,
+
+    /// Print the Boolean component associated to each threshold
+    #[structopt(short, long)]
+    mapping: bool,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["boolean"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+        let smodel = context.get_model()?;
+        let boolean = Booleanizer::new(&smodel.borrow())
+            .set_encoding(config.encoding)
+            .apply();
+
+        if config.mapping {
+            let booleanization = boolean.booleanization().unwrap();
+            let original = booleanization.original_variables();
+            for (vid, cid) in booleanization.mapping() {
+                println!("{} = {}", original.get_name(*vid), boolean.get_name(*cid));
+            }
+        }
+
+        match &config.name {
+            None => smodel.borrow_mut().replace_with("booleanize", boolean),
+            Some(name) => context.set_model(SharedModel::with(boolean), Some(name))?,
+        }
+        Ok(())
+    }
+}
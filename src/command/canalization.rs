@@ -0,0 +1,55 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::func::{Fmt, Formula, Grouped, VariableNamer};
+use crate::helper::error::EmptyLomakResult;
+use crate::variables::GroupedVariables;
+
+static NAME: &str = "canalization";
+static ABOUT: &str = "Identify canalizing inputs and the canalization depth of each function";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Only list nested canalizing functions
+    #[structopt(short, long)]
+    nested: bool,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["canalizing"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+
+        let smodel = context.get_model()?;
+        let model = smodel.borrow();
+
+        for vid in model.variables() {
+            let canalization = Formula::from(model.get_var_rule(*vid)).canalization();
+            if config.nested && !canalization.is_nested() {
+                continue;
+            }
+            println!(
+                "{}: {}",
+                model.get_name(*vid),
+                Fmt(|f| canalization.gfmt(model.as_namer(), f))
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use std::ffi::OsString;
+use std::ops::Deref;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::{EmptyLomakResult, GenericError};
+use crate::model::actions::check::{check_model, Severity};
+
+static NAME: &str = "check";
+static ABOUT: &str = "Report potential problems in the model";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Minimal severity of the reported problems: info, warning or error
+    #[structopt(short, long, default_value = "info")]
+    level: Severity,
+
+    /// Fail if a problem of at least this severity is found
+    #[structopt(short, long, default_value = "error")]
+    fail: Severity,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["lint"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+
+        let smodel = context.get_model()?;
+        let diagnostics = check_model(smodel.borrow().deref());
+        for diagnostic in diagnostics.iter().filter(|d| d.severity >= config.level) {
+            println!("{}", diagnostic);
+        }
+
+        let failed = diagnostics
+            .iter()
+            .filter(|d| d.severity >= config.fail)
+            .count();
+        if failed > 0 {
+            return Err(GenericError::new(format!(
+                "Found {} problems of severity {} or higher",
+                failed, config.fail
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
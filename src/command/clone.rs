@@ -0,0 +1,39 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::EmptyLomakResult;
+use crate::model::{QModel, SharedModel};
+
+static NAME: &str = "clone";
+static ABOUT: &str = "Copy the current model under a new name";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// The name of the copy
+    name: String,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["copy"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+        let smodel = context.get_model()?;
+        let copy = QModel::clone(&smodel.borrow());
+        context.set_model(SharedModel::with(copy), Some(&config.name))
+    }
+}
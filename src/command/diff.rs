@@ -0,0 +1,67 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::{EmptyLomakResult, GenericError};
+use crate::model::actions::diff::ModelDiff;
+use crate::model::io;
+
+static NAME: &str = "diff";
+static ABOUT: &str = "Compare the current model with another model";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Compare with a named model instead of a file
+    #[structopt(short, long)]
+    with: Option<String>,
+
+    /// Enforce format instead of using file extensions
+    #[structopt(short = "F", long)]
+    format: Option<String>,
+
+    /// Compare with a model file, or compare two files
+    #[structopt(max_values = 2)]
+    files: Vec<String>,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["compare"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+        let fmt = config.format.as_deref();
+
+        let (original, model) = match (config.files.as_slice(), &config.with) {
+            ([first, second], None) => (io::load_model(first, fmt)?, io::load_model(second, fmt)?),
+            ([file], None) => (context.get_model()?, io::load_model(file, fmt)?),
+            ([], Some(name)) => (context.get_model()?, context.get_named_model(Some(name))?),
+            _ => {
+                return Err(GenericError::new(
+                    "Compare with a single named model or model file".to_owned(),
+                )
+                .into())
+            }
+        };
+
+        let diff = ModelDiff::new(&original.borrow(), &model.borrow());
+        if diff.is_empty() {
+            println!("No difference");
+        } else {
+            print!("{}", diff);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::EmptyLomakResult;
+use crate::model::modifier::extract::Extractor;
+
+static NAME: &str = "extract";
+static ABOUT: &str = "Keep a selection of components, their regulators become inputs";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Select all components with a name matching a regular expression
+    #[structopt(short, long)]
+    regex: Vec<String>,
+
+    /// Select a component and its regulators up to the given depth
+    #[structopt(short, long, use_delimiter = true)]
+    upstream: Vec<String>,
+
+    /// Select a component and its targets up to the given depth
+    #[structopt(short, long, use_delimiter = true)]
+    downstream: Vec<String>,
+
+    /// Depth of the upstream and downstream neighbourhoods
+    #[structopt(long, default_value = "1")]
+    depth: usize,
+
+    /// Fix all inputs at the given level instead of leaving them free (must be valid for each input)
+    #[structopt(long)]
+    input_level: Option<usize>,
+
+    /// Fix the level of specific inputs: NAME=LEVEL
+    #[structopt(short, long, use_delimiter = true)]
+    fix: Vec<String>,
+
+    /// Names of the selected components
+    components: Vec<String>,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["submodel"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+
+        let smodel = context.get_model()?;
+        let mut model = smodel.borrow_mut();
+        let mut extractor = Extractor::new(&mut model);
+        for name in config.components.iter() {
+            extractor.select(name)?;
+        }
+        for pattern in config.regex.iter() {
+            if extractor.select_regex(pattern)? == 0 {
+                eprintln!("No component matching {}", pattern);
+            }
+        }
+        for name in config.upstream.iter() {
+            extractor.select_upstream(name, config.depth)?;
+        }
+        for name in config.downstream.iter() {
+            extractor.select_downstream(name, config.depth)?;
+        }
+
+        extractor.set_input_level(config.input_level);
+        for fixed in config.fix.iter() {
+            extractor.fix_input(fixed)?;
+        }
+
+        let count = extractor.apply()?;
+        eprintln!("Removed {} components", count);
+        Ok(())
+    }
+}
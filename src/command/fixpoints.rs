@@ -1,10 +1,17 @@
 use std::ffi::OsString;
+use std::fs::File;
+use std::io::BufWriter;
+use std::ops::Deref;
 
 use structopt::StructOpt;
 
 use crate::command::{CLICommand, CommandContext};
-use crate::helper::error::EmptyLomakResult;
-use crate::model::actions::fixpoints::FixedBuilder;
+use crate::func::pattern::Pattern;
+use crate::func::{Fmt, Grouped, VariableNamer};
+use crate::helper::error::{EmptyLomakResult, GenericError, LomakResult};
+use crate::model::actions::fixpoints::{FixedBuilder, FixedPoints};
+use crate::model::actions::inputs::{input_conditions, InputCondition};
+use crate::model::{QModel, SharedModel};
 
 static NAME: &str = "fixpoints";
 static ABOUT: &str = "Compute the fixed points of the model";
@@ -20,9 +27,25 @@ struct Config {
     #[structopt(short, long)]
     displayed: Option<Vec<String>>,
 
-    /// Enforce additional constraints
+    /// Enforce additional constraints, as named (A=1,B=0) or ternary (1-0) patterns
     #[structopt(short, long)]
     enforce: Option<Vec<String>>,
+
+    /// Export the problem to a DIMACS file instead of solving it
+    #[structopt(long)]
+    dimacs: Option<String>,
+
+    /// Map the fixed points of a reduced or booleanized model back to the original model
+    #[structopt(long)]
+    expand: bool,
+
+    /// Use an external SAT solver command instead of clingo
+    #[structopt(long)]
+    sat: Option<String>,
+
+    /// Fix input levels (A=1) or enumerate all combinations of free inputs (all)
+    #[structopt(short, long, use_delimiter = true)]
+    inputs: Vec<String>,
 }
 
 pub struct CLI;
@@ -41,30 +64,80 @@ impl CLICommand for CLI {
 
     fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
         let config: Config = Config::from_iter(args);
-
-        // Create the fixpoint builder
         let smodel = context.get_model()?;
-        let mut builder = FixedBuilder::new(smodel);
 
-        // Apply extra restrictions if any
-        if let Some(enforce) = config.enforce {
-            for r in enforce {
-                // enforce a constraint by restricting the opposite
-                builder.restrict_by_name(&r, false)?;
+        if let Some(filename) = &config.dimacs {
+            if !config.inputs.is_empty() {
+                return Err(GenericError::new(
+                    "Input conditions can not be exported to DIMACS".to_owned(),
+                )
+                .into());
             }
+            let builder = create_builder(&config, &smodel)?;
+            let mut out = BufWriter::new(File::create(filename)?);
+            return builder.to_cnf().write_dimacs(&mut out);
         }
 
-        // Search the fixpoints and retrieve the results
-        let mut result = builder.solve(config.max);
-
-        // Select the listed variables and display the results
-        if let Some(display) = config.displayed {
-            result.set_displayed_names(Some(display));
+        let conditions = if config.inputs.is_empty() {
+            vec![]
+        } else {
+            input_conditions(&smodel.borrow(), &config.inputs)?
+        };
+        if conditions.iter().all(InputCondition::is_empty) {
+            let result = search(&config, &smodel, &smodel.borrow())?;
+            println!("Fixed points:");
+            println!("{}", result);
+            println!("---");
+            return Ok(());
         }
-        println!("Fixed points:");
-        println!("{}", result);
-        println!("---");
 
+        // Search the fixed points for each input condition, locking the inputs discards the
+        // mappings of reduced or booleanized models: expand the results using the original model
+        let model = smodel.borrow();
+        for condition in conditions {
+            let fixed = SharedModel::with(condition.apply(&model));
+            let result = search(&config, &fixed, &model)?;
+            println!(
+                "Fixed points for inputs {}:",
+                Fmt(|f| condition.gfmt(model.as_namer(), f))
+            );
+            println!("{}", result);
+            println!("---");
+        }
         Ok(())
     }
 }
+
+/// Create the fixpoint builder and apply extra restrictions if any
+fn create_builder(config: &Config, smodel: &SharedModel) -> LomakResult<FixedBuilder> {
+    let mut builder = FixedBuilder::new(smodel.clone());
+    if let Some(enforce) = &config.enforce {
+        let enforced = Pattern::parse_all(enforce, smodel.borrow().deref())?;
+        builder.enforce(&enforced);
+    }
+    Ok(builder)
+}
+
+/// Search the fixpoints and retrieve the results, expanded using the mappings of the original model
+fn search(config: &Config, smodel: &SharedModel, original: &QModel) -> LomakResult<FixedPoints> {
+    let builder = create_builder(config, smodel)?;
+    let mut result = match &config.sat {
+        None => builder.solve(config.max),
+        Some(command) => builder.solve_sat(command, config.max)?,
+    };
+
+    if config.expand {
+        if let Some(reduction) = original.reduction() {
+            result = reduction.expand_fixed(&result);
+        }
+        if let Some(booleanization) = original.booleanization() {
+            result = booleanization.expand_fixed(&result);
+        }
+    }
+
+    // Select the listed variables
+    if let Some(display) = &config.displayed {
+        result.set_displayed_names(Some(display.clone()));
+    }
+    Ok(result)
+}
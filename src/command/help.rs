@@ -31,6 +31,8 @@ impl command::CLICommand for CLI {
 
         command::COMMANDS.print_commands();
         println!();
+        println!("All commands use the default model, use \"--model NAME\" to select a named model");
+        println!();
         io::print_formats();
         Ok(())
     }
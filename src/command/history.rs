@@ -0,0 +1,59 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::EmptyLomakResult;
+
+static NAME: &str = "history";
+static ABOUT: &str = "List the modifications of the current model";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Stop recording the following modifications
+    #[structopt(long)]
+    disable: bool,
+
+    /// Resume the recording of modifications
+    #[structopt(long, conflicts_with = "disable")]
+    enable: bool,
+
+    /// Forget the recorded modifications
+    #[structopt(long)]
+    clear: bool,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+        let smodel = context.get_model()?;
+        let mut model = smodel.borrow_mut();
+
+        if config.clear {
+            model.clear_history();
+        }
+        if config.disable || config.enable {
+            model.set_recording(config.enable);
+            return Ok(());
+        }
+
+        let history = model.history();
+        for (idx, edit) in history.done().enumerate() {
+            println!("{:4} {}", idx + 1, edit);
+        }
+        for edit in history.undone() {
+            println!("   - {} (undone)", edit);
+        }
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ use structopt::StructOpt;
 use crate::command::{CLICommand, CommandContext};
 use crate::helper::error::EmptyLomakResult;
 use crate::model::io;
+use crate::variables::NamePolicy;
 
 static NAME: &str = "load";
 static ABOUT: &str = "Load a model from a file";
@@ -16,6 +17,14 @@ struct Config {
     #[structopt(short = "F", long)]
     format: Option<String>,
 
+    /// Store the model under a name instead of replacing the default model
+    #[structopt(long = "as")]
+    name: Option<String>,
+
+    /// Normalisation of invalid names: replace, strip, generic
+    #[structopt(long, default_value = "replace")]
+    names: NamePolicy,
+
     /// File containing the model
     filename: String,
 }
@@ -33,8 +42,7 @@ impl CLICommand for CLI {
         // Start by parsing arguments to handle help without any context
         let config: Config = Config::from_iter(args);
 
-        let model = io::load_model(&config.filename, config.format.as_deref())?;
-        context.set_model(model, None);
-        Ok(())
+        let model = io::load_model_with(&config.filename, config.format.as_deref(), config.names)?;
+        context.set_model(model, config.name.as_deref())
     }
 }
@@ -0,0 +1,85 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::{EmptyLomakResult, GenericError};
+use crate::model::io;
+use crate::model::modifier::merge::{MergePolicy, Merger};
+use crate::model::QModel;
+use std::ops::DerefMut;
+
+static NAME: &str = "merge";
+static ABOUT: &str = "Import the components of another model into the current one";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Import a named model instead of a file
+    #[structopt(short, long)]
+    with: Option<String>,
+
+    /// Enforce format instead of using file extension
+    #[structopt(short = "F", long)]
+    format: Option<String>,
+
+    /// Combination of the rules of shared components: left, right, or, and, fail
+    #[structopt(long, default_value = "fail")]
+    policy: MergePolicy,
+
+    /// Prefix for the names of imported components which are not shared
+    #[structopt(short, long)]
+    prefix: Option<String>,
+
+    /// Restrict the list of shared components
+    #[structopt(short, long, use_delimiter = true)]
+    shared: Vec<String>,
+
+    /// File containing the imported model
+    filename: Option<String>,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["import"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+
+        let other = match (&config.filename, &config.with) {
+            (Some(filename), None) => io::load_model(filename, config.format.as_deref())?,
+            (None, Some(name)) => context.get_named_model(Some(name))?,
+            _ => {
+                return Err(
+                    GenericError::new("Import a single named model or model file".to_owned())
+                        .into(),
+                )
+            }
+        };
+
+        let smodel = context.get_model()?;
+        // Copy the imported model as it may be the current one
+        let other = QModel::clone(&other.borrow());
+        let mut model = smodel.borrow_mut();
+        let mut merger = Merger::new(model.deref_mut());
+        merger.set_policy(config.policy);
+        if let Some(prefix) = &config.prefix {
+            merger.set_prefix(prefix);
+        }
+        for name in &config.shared {
+            merger.share(name);
+        }
+        merger.apply(&other)?;
+        Ok(())
+    }
+}
@@ -4,9 +4,13 @@
 //! defined in a private submodule, is a thin wrapper over core API features.
 //!
 //! The CLI enables to chain several commands, each can use and modify a global context, currently
-//!limited to a shared model. The global CLI will thus start by searching command names in the full
-//! list of arguments. The arguments between two successive commands define the arguments of the
-//! first command.
+//! limited to a collection of named models. The global CLI will thus start by searching command names
+//! in the full list of arguments. The arguments between two successive commands define the arguments
+//! of the first command.
+//!
+//! Commands use the default (unnamed) model, unless another model is selected with the
+//! ```--model NAME``` option, which is available for all commands. As command names delimit the
+//! arguments of each command, they can not be used as model names.
 //!
 //! # Example
 //!
@@ -18,6 +22,13 @@
 //! * **load** ```-f sbml model.xml```
 //! * **perturbation** ```--ko Cmp3```
 //! * **fixpoints**
+//!
+//! Named models enable to compare variants of the same model:
+//!
+//! ```lomak load model.xml clone ko perturbation --model ko --ko Cmp3 fixpoints fixpoints --model ko```
+
+// Each command module exposes its entry point as a `CLI` struct
+#![allow(clippy::upper_case_acronyms)]
 
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -25,7 +36,7 @@ use std::env;
 use std::ffi::OsString;
 use std::sync::Arc;
 
-use crate::helper::error::{EmptyLomakResult, LomakError, LomakResult};
+use crate::helper::error::{EmptyLomakResult, GenericError, LomakError, LomakResult};
 use crate::model::SharedModel;
 
 // Use a macro to load all command modules and add them to the list of available commands
@@ -45,14 +56,27 @@ cmdmods!(
     help,
     load,
     buffer,
+    merge,
+    extract,
     perturbation,
+    reduce,
+    booleanize,
+    multivalued,
     rename,
+    undo,
+    history,
     fixpoints,
     reach,
     trapspaces,
     primes,
+    canalization,
+    stats,
+    check,
     save,
-    show
+    show,
+    clone,
+    diff,
+    sort
 );
 
 pub fn help_cmd(context: &mut CommandContext) -> EmptyLomakResult {
@@ -95,9 +119,7 @@ impl CommandManager {
     }
 
     pub fn get_command(&self, name: &str) -> Option<Arc<dyn CLICommand>> {
-        self.services
-            .get(self.unroll_alias(name))
-            .map(|c| Arc::clone(c))
+        self.services.get(self.unroll_alias(name)).map(Arc::clone)
     }
 
     pub fn print_commands(&self) {
@@ -113,22 +135,37 @@ impl CommandManager {
 #[derive(Default)]
 pub struct CommandContext {
     models: HashMap<String, SharedModel>,
+    selected: Option<String>,
 }
 
 impl CommandContext {
+    /// Retrieve the model selected for the current command, or the default model
     pub fn get_model(&self) -> LomakResult<SharedModel> {
-        self.get_named_model(None)
+        self.get_named_model(self.selected.as_deref())
     }
 
     pub fn get_named_model(&self, name: Option<&str>) -> LomakResult<SharedModel> {
-        match self.models.get(name.unwrap_or("")) {
-            Some(m) => Ok(m.clone()),
-            None => Err(LomakError::MissingModel()),
+        match (self.models.get(name.unwrap_or("")), name) {
+            (Some(m), _) => Ok(m.clone()),
+            (None, Some(name)) if !name.is_empty() => {
+                Err(LomakError::UnknownModel(name.to_owned()))
+            }
+            (None, _) => Err(LomakError::MissingModel()),
         }
     }
 
-    pub fn set_model(&mut self, model: SharedModel, name: Option<&str>) {
+    /// Select the model used by the next command, or use the default model
+    pub fn select_model(&mut self, name: Option<String>) {
+        self.selected = name;
+    }
+
+    /// Store a model as the default model or under a name which is not a command name
+    pub fn set_model(&mut self, model: SharedModel, name: Option<&str>) -> EmptyLomakResult {
+        if let Some(name) = name {
+            check_model_name(name)?;
+        }
         self.models.insert(name.unwrap_or("").to_owned(), model);
+        Ok(())
     }
 }
 
@@ -173,9 +210,12 @@ impl SelectedArgs {
             Some(c) => c,
         };
 
-        let next_args = self.scan();
+        let (next_args, selected) = extract_model_option(self.scan())?;
 
-        cmd.run(context, next_args)
+        context.select_model(selected);
+        let result = cmd.run(context, &next_args);
+        context.select_model(None);
+        result
     }
 
     pub fn scan(&mut self) -> &[OsString] {
@@ -196,8 +236,108 @@ impl SelectedArgs {
     }
 }
 
+/// Reject model names which would be taken for commands when splitting the arguments
+fn check_model_name(name: &str) -> EmptyLomakResult {
+    if COMMANDS.contains(name) {
+        return Err(GenericError::new(format!(
+            "The command name {} can not be used as a model name",
+            name
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Remove the global ```--model NAME``` option from the arguments of a command
+fn extract_model_option(args: &[OsString]) -> LomakResult<(Vec<OsString>, Option<String>)> {
+    let mut selected = None;
+    let mut filtered = Vec::with_capacity(args.len());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.to_str() {
+            Some("--model") => match iter.next().and_then(|s| s.to_str()) {
+                None => return Err(GenericError::new("Missing model name".to_owned()).into()),
+                Some(name) => selected = Some(name.to_owned()),
+            },
+            Some(s) if s.starts_with("--model=") => selected = Some(s[8..].to_owned()),
+            _ => filtered.push(arg.clone()),
+        }
+    }
+    Ok((filtered, selected))
+}
+
 impl Default for SelectedArgs {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use crate::command::{check_model_name, extract_model_option, CommandContext, SelectedArgs};
+    use crate::model::SharedModel;
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn split_arguments() {
+        let mut args = SelectedArgs {
+            all_args: os_args(&[
+                "lomak",
+                "load",
+                "m.bnet",
+                "clone",
+                "ko",
+                "perturbation",
+                "--model",
+                "ko",
+                "-k",
+                "A",
+                "fixpoints",
+                "--model=ko",
+            ]),
+            next_slice: 0,
+        };
+        assert_eq!(args.scan(), os_args(&["lomak"]).as_slice());
+        assert_eq!(args.scan(), os_args(&["load", "m.bnet"]).as_slice());
+        assert_eq!(args.scan(), os_args(&["clone", "ko"]).as_slice());
+
+        let (filtered, selected) = extract_model_option(args.scan()).unwrap();
+        assert_eq!(filtered, os_args(&["perturbation", "-k", "A"]));
+        assert_eq!(selected.as_deref(), Some("ko"));
+
+        let (filtered, selected) = extract_model_option(args.scan()).unwrap();
+        assert_eq!(filtered, os_args(&["fixpoints"]));
+        assert_eq!(selected.as_deref(), Some("ko"));
+        assert!(!args.has_next());
+
+        // A model name matching a command starts a new slice
+        let mut args = SelectedArgs {
+            all_args: os_args(&["fixpoints", "--model", "reach"]),
+            next_slice: 0,
+        };
+        assert!(extract_model_option(args.scan()).is_err());
+        assert_eq!(args.scan(), os_args(&["reach"]).as_slice());
+    }
+
+    #[test]
+    fn reject_command_names() {
+        assert!(check_model_name("ko").is_ok());
+        assert!(check_model_name("reach").is_err());
+        assert!(check_model_name("copy").is_err());
+
+        let mut context = CommandContext::default();
+        assert!(context
+            .set_model(SharedModel::default(), Some("fixpoints"))
+            .is_err());
+        assert!(context.get_named_model(Some("fixpoints")).is_err());
+        context
+            .set_model(SharedModel::default(), Some("wt"))
+            .unwrap();
+        assert!(context.get_named_model(Some("wt")).is_ok());
+    }
+}
@@ -0,0 +1,64 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::{EmptyLomakResult, GenericError};
+use crate::model::modifier::multivalued::ThresholdGroups;
+
+static NAME: &str = "multivalued";
+static ABOUT: &str = "Merge groups of Boolean components into multivalued components";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Detect groups from the names of components (A_1, A_2 or A_b1, A_b2)
+    #[structopt(short, long)]
+    detect: bool,
+
+    /// Groups of Boolean components, from the lowest to the highest threshold: NAME=A_1,A_2
+    groups: Vec<String>,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["unbooleanize"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+
+        let smodel = context.get_model()?;
+        let mut model = smodel.borrow_mut();
+        let mut groups = ThresholdGroups::new(&mut model);
+        for group in config.groups.iter() {
+            let (name, members) = match group.split_once('=') {
+                Some((name, members)) => (name.trim(), members),
+                None => {
+                    return Err(GenericError::new(format!("Invalid group: {}", group)).into())
+                }
+            };
+            let members: Vec<&str> = members.split(',').map(|m| m.trim()).collect();
+            groups.add_group(name, &members)?;
+        }
+        if config.detect || config.groups.is_empty() {
+            groups.detect();
+            for e in groups.skipped() {
+                eprintln!("Skipped a detected group: {}", e);
+            }
+        }
+
+        let count = groups.apply()?;
+        eprintln!("Merged {} groups", count);
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
 use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
 
 use structopt::StructOpt;
 
@@ -13,13 +15,28 @@ static ABOUT: &str = "Apply a perturbation to one or several components or inter
 #[derive(Debug, StructOpt)]
 #[structopt(name=NAME, about=ABOUT)]
 struct Config {
-    /// Components to knock-out (fix level to 0)
+    /// Components to knock-out (fix level to 0), or interactions (A@B, A:2@B)
     #[structopt(long)]
     ko: Vec<String>,
 
-    /// Components to knock-in (fix level to 1)
+    /// Components to knock-in (fix level to 1), or interactions (A@B, A:2@B)
     #[structopt(long)]
     ki: Vec<String>,
+
+    /// Fix the level of components (A=2)
+    #[structopt(long)]
+    fix: Vec<String>,
+
+    /// Restrict the level of components to a range (A=1..2)
+    #[structopt(long)]
+    range: Vec<String>,
+
+    /// Load a combined perturbation from a file
+    #[structopt(short, long, parse(from_os_str))]
+    file: Option<PathBuf>,
+
+    /// Perturbations using the compact syntax (A%0, A%1:2, A@B%0, A:2@B%1)
+    perturbations: Vec<String>,
 }
 
 pub struct CLI;
@@ -48,6 +65,19 @@ impl CLICommand for CLI {
         for s in &config.ki {
             perturbator.guess_lock(s, true)?;
         }
+        for s in &config.fix {
+            perturbator.parse_fixed(s)?;
+        }
+        for s in &config.range {
+            perturbator.parse_range(s)?;
+        }
+        for s in &config.perturbations {
+            perturbator.parse(s)?;
+        }
+        if let Some(path) = &config.file {
+            let text = fs::read_to_string(path)?;
+            perturbator.parse_list(&text)?;
+        }
 
         // Apply all perturbations
         perturbator.apply();
@@ -4,10 +4,12 @@ use structopt::StructOpt;
 
 use crate::command::{CLICommand, CommandContext};
 use crate::func::state::State;
-use crate::helper::error::{EmptyLomakResult, GenericError};
+use crate::func::pattern::Pattern;
+use crate::func::{Fmt, Grouped, VariableNamer};
+use crate::helper::error::{EmptyLomakResult, LomakResult, ParseError};
+use crate::model::actions::inputs::{input_conditions, InputCondition};
 use crate::model::actions::reach;
 use crate::model::QModel;
-use crate::variables::GroupedVariables;
 use std::ops::Deref;
 
 static NAME: &str = "reach";
@@ -16,13 +18,17 @@ static ABOUT: &str = "Reachability analysis";
 #[derive(Debug, StructOpt)]
 #[structopt(name=NAME, about=ABOUT)]
 struct Config {
-    /// Active components in the initial state
+    /// Initial state, as named (A=1,B=0) or ternary (1-0) patterns, unset variables are inactive
     #[structopt(short, long)]
     initial: Option<Vec<String>>,
 
-    /// Active components in the target state
+    /// Target state, as named (A=1,B=0) or ternary (1-0) patterns, unset variables are inactive
     #[structopt(short, long)]
     target: Option<Vec<String>>,
+
+    /// Fix input levels (A=1) or enumerate all combinations of free inputs (all)
+    #[structopt(long, use_delimiter = true)]
+    inputs: Vec<String>,
 }
 
 pub struct CLI;
@@ -40,24 +46,43 @@ impl CLICommand for CLI {
         let smodel = context.get_model()?;
         let model = smodel.borrow();
 
-        let init = state_from_cli(model.deref(), config.initial)?;
-        let target = state_from_cli(model.deref(), config.target)?;
-
-        if reach::most_permissive_reach(model.deref(), init, target) {
-            println!("Reachable!");
+        let conditions = if config.inputs.is_empty() {
+            vec![]
         } else {
-            println!("NOT Reachable!");
+            input_conditions(model.deref(), &config.inputs)?
+        };
+        if conditions.iter().all(InputCondition::is_empty) {
+            println!("{}", search(&config, model.deref())?);
+            return Ok(());
+        }
+
+        // Check the reachability for each input condition
+        for condition in conditions {
+            let fixed = condition.apply(model.deref());
+            println!(
+                "Reachability for inputs {}:",
+                Fmt(|f| condition.gfmt(model.as_namer(), f))
+            );
+            println!("{}", search(&config, &fixed)?);
         }
         Ok(())
     }
 }
 
-fn state_from_cli(model: &QModel, names: Option<Vec<String>>) -> Result<State, GenericError> {
-    let mut state = State::new();
-    if let Some(names) = names {
-        for name in &names {
-            state.insert(model.get_handle_res(name)?);
-        }
+fn search(config: &Config, model: &QModel) -> LomakResult<&'static str> {
+    let init = state_from_cli(model, config.initial.clone())?;
+    let target = state_from_cli(model, config.target.clone())?;
+
+    if reach::most_permissive_reach(model, init, target) {
+        Ok("Reachable!")
+    } else {
+        Ok("NOT Reachable!")
+    }
+}
+
+fn state_from_cli(model: &QModel, patterns: Option<Vec<String>>) -> Result<State, ParseError> {
+    match patterns {
+        None => Ok(State::new()),
+        Some(patterns) => Ok(Pattern::parse_all(&patterns, model)?.positive().clone()),
     }
-    Ok(state)
 }
@@ -0,0 +1,60 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::EmptyLomakResult;
+use crate::model::modifier::reduction::Reducer;
+
+static NAME: &str = "reduce";
+static ABOUT: &str = "Reduce the model by eliminating components";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Eliminate outputs (components which do not regulate any other component)
+    #[structopt(short, long)]
+    outputs: bool,
+
+    /// Eliminate fixed components and propagate their values (components without rules are fixed at 0)
+    #[structopt(short, long)]
+    fixed: bool,
+
+    /// Eliminate selected components
+    #[structopt(short, long, use_delimiter = true)]
+    remove: Vec<String>,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["reduction"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+
+        let smodel = context.get_model()?;
+        let mut model = smodel.borrow_mut();
+        let mut reducer = Reducer::new(&mut model);
+        for name in config.remove.iter() {
+            reducer.remove(name)?;
+        }
+        reducer.set_outputs(config.outputs).set_fixed(config.fixed);
+        let count = reducer.apply()?;
+        for name in reducer.skipped() {
+            eprintln!("Skipped {}: it became self-regulated", name);
+        }
+        eprintln!("Eliminated {} components", count);
+
+        Ok(())
+    }
+}
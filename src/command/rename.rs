@@ -1,10 +1,11 @@
 use std::ffi::OsString;
+use std::fs;
 
 use structopt::StructOpt;
 
 use crate::command::{CLICommand, CommandContext};
-use crate::helper::error::{EmptyLomakResult, GenericError, LomakError};
-use crate::variables::GroupedVariables;
+use crate::helper::error::{EmptyLomakResult, GenericError};
+use crate::model::modifier::rename::Renamer;
 use std::ops::DerefMut;
 
 static NAME: &str = "rename";
@@ -13,11 +14,16 @@ static ABOUT: &str = "Rename one or several components";
 #[derive(Debug, StructOpt)]
 #[structopt(name=NAME, about=ABOUT)]
 struct Config {
-    /// The original name
-    source: String,
+    /// File with two columns: the current and new names
+    #[structopt(short, long)]
+    file: Option<String>,
 
-    /// The target name
-    target: String,
+    /// Substitution applied to all names: 's/pattern/replacement/', escape the delimiter with a backslash
+    #[structopt(short, long)]
+    regex: Vec<String>,
+
+    /// Pairs of original and target names
+    names: Vec<String>,
 }
 
 pub struct CLI;
@@ -34,14 +40,30 @@ impl CLICommand for CLI {
     fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
         // Start by parsing arguments to handle help without any context
         let config: Config = Config::from_iter(args);
+        if !config.names.len().is_multiple_of(2) {
+            return Err(GenericError::new(
+                "Names should be given as pairs of original and target names".to_owned(),
+            )
+            .into());
+        }
 
         let smodel = context.get_model()?;
-
-        // TODO: multiple rename actions ?
         let mut model = smodel.borrow_mut();
-        match model.deref_mut().rename(&config.source, &config.target) {
-            Err(s) => Err(LomakError::Generic(GenericError::new(s.to_owned()))),
-            Ok(_) => Ok(()),
+        let mut renamer = Renamer::new(model.deref_mut());
+        for pair in config.names.chunks(2) {
+            renamer.rename(&pair[0], &pair[1])?;
         }
+        if let Some(path) = &config.file {
+            renamer.parse_mapping(&fs::read_to_string(path)?)?;
+        }
+        for substitution in config.regex.iter() {
+            if renamer.parse_substitution(substitution)? == 0 {
+                eprintln!("No component matching {}", substitution);
+            }
+        }
+
+        let count = renamer.apply()?;
+        eprintln!("Renamed {} components", count);
+        Ok(())
     }
 }
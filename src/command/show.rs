@@ -16,6 +16,9 @@ struct Config {
     booleanized: bool,
     #[structopt(short, long)]
     layout: bool,
+    /// Show the origin and the applied modifiers
+    #[structopt(short, long)]
+    provenance: bool,
 }
 
 pub struct CLI;
@@ -54,6 +57,10 @@ impl CLICommand for CLI {
                 }
             }
         }
+
+        if config.provenance {
+            print!("{}", model.provenance());
+        }
         Ok(())
     }
 }
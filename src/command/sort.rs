@@ -0,0 +1,62 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::{EmptyLomakResult, GenericError};
+use crate::variables::GroupedVariables;
+
+static NAME: &str = "sort";
+static ABOUT: &str = "Change the order of the components";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Place components after their regulators instead of sorting them by name
+    #[structopt(short, long)]
+    graph: bool,
+
+    /// Explicit order: all components, listed exactly once
+    components: Vec<String>,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["reorder"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+
+        let smodel = context.get_model()?;
+        let mut model = smodel.borrow_mut();
+        if !config.components.is_empty() {
+            if config.graph {
+                return Err(GenericError::new(
+                    "Use either an explicit order or the graph order".to_owned(),
+                )
+                .into());
+            }
+            let order = config
+                .components
+                .iter()
+                .map(|name| model.get_handle_res(name))
+                .collect::<Result<Vec<usize>, GenericError>>()?;
+            model.reorder_components(&order)?;
+        } else if config.graph {
+            model.sort_components_by_graph();
+        } else {
+            model.sort_components_by_name();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::ffi::OsString;
+use std::ops::Deref;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::func::{Fmt, Grouped, VariableNamer};
+use crate::helper::error::EmptyLomakResult;
+use crate::model::actions::inputs::{input_conditions, InputCondition};
+use crate::model::actions::stats::{function_stats, ModelStats};
+use crate::model::QModel;
+use crate::variables::GroupedVariables;
+
+static NAME: &str = "stats";
+static ABOUT: &str = "Show statistics on the model and its functions";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Only show the global statistics
+    #[structopt(short, long)]
+    summary: bool,
+
+    /// Fix input levels (A=1) or enumerate all combinations of free inputs (all)
+    #[structopt(short, long, use_delimiter = true)]
+    inputs: Vec<String>,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["statistics"]
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+
+        let smodel = context.get_model()?;
+        let model = smodel.borrow();
+
+        let conditions = if config.inputs.is_empty() {
+            vec![]
+        } else {
+            input_conditions(model.deref(), &config.inputs)?
+        };
+        if conditions.iter().all(InputCondition::is_empty) {
+            return show_stats(&config, model.deref());
+        }
+
+        // Show the statistics for each input condition
+        for condition in conditions {
+            let fixed = condition.apply(model.deref());
+            println!(
+                "Statistics for inputs {}:",
+                Fmt(|f| condition.gfmt(model.as_namer(), f))
+            );
+            show_stats(&config, &fixed)?;
+            println!();
+        }
+        Ok(())
+    }
+}
+
+fn show_stats(config: &Config, model: &QModel) -> EmptyLomakResult {
+    if !config.summary {
+        println!(
+            "{:20} {:>9} {:>12} {:>8} {:>11}",
+            "variable", "essential", "count", "bias", "sensitivity"
+        );
+        for (vid, stats) in function_stats(model)? {
+            println!(
+                "{:20} {:>9} {:>12} {:>8.4} {:>11.4}",
+                model.get_name(vid),
+                stats.essential,
+                stats.count,
+                stats.bias,
+                stats.sensitivity
+            );
+        }
+        println!();
+    }
+
+    let stats = ModelStats::new(model);
+    println!("Components: {}", stats.components);
+    println!("Variables:  {}", stats.variables);
+    println!("Edges:      {}", stats.edges);
+    println!("Inputs:     {}", stats.inputs);
+
+    Ok(())
+}
@@ -1,10 +1,16 @@
 use std::ffi::OsString;
+use std::ops::Deref;
 
 use structopt::StructOpt;
 
 use crate::command::{CLICommand, CommandContext};
-use crate::helper::error::EmptyLomakResult;
+use crate::func::pattern::Pattern;
+use crate::func::{Fmt, Grouped, VariableNamer};
+use crate::helper::error::{EmptyLomakResult, LomakResult};
+use crate::model::actions::fixpoints::FixedPoints;
+use crate::model::actions::inputs::{input_conditions, InputCondition};
 use crate::model::actions::trapspaces::TrapspacesBuilder;
+use crate::model::SharedModel;
 
 static NAME: &str = "trapspaces";
 static ABOUT: &str = "Compute the trapspaces (stable patterns) of the model";
@@ -12,7 +18,7 @@ static ABOUT: &str = "Compute the trapspaces (stable patterns) of the model";
 #[derive(Debug, StructOpt)]
 #[structopt(name=NAME, about=ABOUT)]
 struct Config {
-    /// Filter the results
+    /// Filter the results, as named (A=1,B=0) or ternary (1-0) patterns
     #[structopt(short, long)]
     filter: Option<Vec<String>>,
 
@@ -35,6 +41,10 @@ struct Config {
     /// Select output components
     #[structopt(short, long)]
     displayed: Option<Vec<String>>,
+
+    /// Fix input levels (A=1) or enumerate all combinations of free inputs (all)
+    #[structopt(short, long, use_delimiter = true)]
+    inputs: Vec<String>,
 }
 
 pub struct CLI;
@@ -55,22 +65,49 @@ impl CLICommand for CLI {
         let config: Config = Config::from_iter(args);
         let smodel = context.get_model()?;
 
-        let mut builder = TrapspacesBuilder::new(smodel);
-        builder.set_percolate(config.percolate);
-
-        if config.elementary {
-            builder.show_elementary();
-        }
-        if config.all {
-            builder.show_all();
+        let conditions = if config.inputs.is_empty() {
+            vec![]
+        } else {
+            input_conditions(&smodel.borrow(), &config.inputs)?
+        };
+        if conditions.iter().all(InputCondition::is_empty) {
+            println!("{}", search(&config, &smodel)?);
+            return Ok(());
         }
 
-        let mut result = builder.solve(config.max);
-        if let Some(display) = config.displayed {
-            result.set_displayed_names(Some(display));
+        // Search the trapspaces for each input condition
+        for condition in conditions {
+            let fixed = SharedModel::with(condition.apply(&smodel.borrow()));
+            let result = search(&config, &fixed)?;
+            let model = smodel.borrow();
+            println!(
+                "Trapspaces for inputs {}:",
+                Fmt(|f| condition.gfmt(model.as_namer(), f))
+            );
+            println!("{}", result);
         }
-        println!("{}", result);
-
         Ok(())
     }
 }
+
+fn search(config: &Config, smodel: &SharedModel) -> LomakResult<FixedPoints> {
+    let mut builder = TrapspacesBuilder::new(smodel.clone());
+    builder.set_percolate(config.percolate);
+    if let Some(filter) = &config.filter {
+        let filter = Pattern::parse_all(filter, smodel.borrow().deref())?;
+        builder.filter_pattern(&filter);
+    }
+
+    if config.elementary {
+        builder.show_elementary();
+    }
+    if config.all {
+        builder.show_all();
+    }
+
+    let mut result = builder.solve(config.max);
+    if let Some(display) = &config.displayed {
+        result.set_displayed_names(Some(display.clone()));
+    }
+    Ok(result)
+}
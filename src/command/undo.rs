@@ -0,0 +1,50 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use crate::command::{CLICommand, CommandContext};
+use crate::helper::error::{EmptyLomakResult, GenericError};
+
+static NAME: &str = "undo";
+static ABOUT: &str = "Revert the last modifications of the current model";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name=NAME, about=ABOUT)]
+struct Config {
+    /// Number of edits to revert
+    #[structopt(short = "n", long, default_value = "1")]
+    count: usize,
+
+    /// Apply again the last reverted edits instead
+    #[structopt(short, long)]
+    redo: bool,
+}
+
+pub struct CLI;
+impl CLICommand for CLI {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn about(&self) -> &'static str {
+        ABOUT
+    }
+
+    fn run(&self, context: &mut CommandContext, args: &[OsString]) -> EmptyLomakResult {
+        let config: Config = Config::from_iter(args);
+        let smodel = context.get_model()?;
+
+        for _ in 0..config.count {
+            let edit = if config.redo {
+                smodel.redo()
+            } else {
+                smodel.undo()
+            };
+            if edit.is_none() {
+                let action = if config.redo { "redo" } else { "undo" };
+                return Err(GenericError::new(format!("Nothing to {}", action)).into());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+//! Identify canalizing inputs and the nested canalizing structure of Boolean functions
+//!
+//! A function is canalizing in an input if fixing this input to its canalizing value
+//! is sufficient to fix the output (the canalized value), whatever the value of other inputs.
+
+use std::fmt;
+use std::rc::Rc;
+
+use crate::func::expr::Expr;
+use crate::func::implicant::Implicants;
+use crate::func::pattern::Pattern;
+use crate::func::{Formula, Grouped, VariableNamer};
+
+/// A canalizing input with the canalizing and canalized values
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Canalizing {
+    pub variable: usize,
+    pub value: bool,
+    pub output: bool,
+}
+
+/// Layers of canalizing inputs.
+///
+/// The first layer contains the canalizing inputs of the function. When all inputs of
+/// a layer take their non-canalizing value, the restricted function can be canalizing in
+/// other inputs which define the next layer.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Canalization {
+    pub layers: Vec<Vec<Canalizing>>,
+    pub essential: usize,
+}
+
+impl Canalization {
+    /// The number of inputs found in all layers
+    pub fn depth(&self) -> usize {
+        let mut vars = Pattern::new();
+        for c in self.layers.iter().flatten() {
+            vars.set(c.variable, true);
+        }
+        vars.len()
+    }
+
+    /// A nested canalizing function is canalizing in all its essential inputs
+    pub fn is_nested(&self) -> bool {
+        self.essential > 0 && self.depth() == self.essential
+    }
+
+    /// The first layer of canalizing inputs
+    pub fn canalizing(&self) -> &[Canalizing] {
+        match self.layers.first() {
+            None => &[],
+            Some(l) => l,
+        }
+    }
+}
+
+/// Extract single-literal implicants, i.e. canalizing inputs
+fn fill_canalizing(primes: &Implicants, output: bool, result: &mut Vec<Canalizing>) {
+    for p in primes.iter().filter(|p| p.len() == 1) {
+        let (variable, value) = match p.positive().iter().next() {
+            Some(u) => (u, true),
+            None => (p.negative().iter().next().unwrap(), false),
+        };
+        result.push(Canalizing {
+            variable,
+            value,
+            output,
+        });
+    }
+}
+
+impl Expr {
+    /// Identify the canalizing inputs of this function.
+    ///
+    /// As the prime implicants are the largest subspaces in which the function is constant,
+    /// canalizing inputs correspond to prime implicants of the function or its negation
+    /// restricted to a single literal.
+    pub fn canalizing_inputs(&self) -> Vec<Canalizing> {
+        let mut result = vec![];
+        fill_canalizing(&self.prime_implicants(), true, &mut result);
+        fill_canalizing(&self.not().prime_implicants(), false, &mut result);
+        result.sort_by_key(|c| (c.variable, c.value));
+        result
+    }
+}
+
+impl Formula {
+    /// Identify the canalizing inputs of this function.
+    pub fn canalizing_inputs(&self) -> Vec<Canalizing> {
+        let expr: Rc<Expr> = self.convert_as();
+        expr.canalizing_inputs()
+    }
+
+    /// Identify the layers of canalizing inputs
+    pub fn canalization(&self) -> Canalization {
+        let mut expr = Expr::clone(&self.convert_as());
+        let essential = self
+            .regulator_effects()
+            .values()
+            .filter(|e| e.is_functional())
+            .count();
+
+        let mut layers = vec![];
+        loop {
+            let layer = expr.canalizing_inputs();
+            if layer.is_empty() {
+                break;
+            }
+
+            // Restrict the function to the non-canalizing values of this layer.
+            // Inputs canalizing for both values make the function constant.
+            let mut restriction = Pattern::new();
+            for c in layer.iter() {
+                if !restriction.is_fixed(c.variable) {
+                    restriction.set(c.variable, !c.value);
+                }
+            }
+            layers.push(layer);
+
+            if let Some(e) = expr.replace_variables(&mut restriction) {
+                expr = e.simplify().unwrap_or(e);
+            }
+        }
+
+        Canalization { layers, essential }
+    }
+}
+
+impl Grouped for Canalizing {
+    fn gfmt(&self, namer: &dyn VariableNamer, f: &mut fmt::Formatter) -> fmt::Result {
+        namer.format_name(f, self.variable)?;
+        write!(f, "={} -> {}", self.value as u8, self.output as u8)
+    }
+}
+
+impl Grouped for Canalization {
+    fn gfmt(&self, namer: &dyn VariableNamer, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "depth {}/{}", self.depth(), self.essential)?;
+        if self.is_nested() {
+            write!(f, " (nested)")?;
+        }
+        for layer in self.layers.iter() {
+            write!(f, " [")?;
+            for (i, c) in layer.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                c.gfmt(namer, f)?;
+            }
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::canalization::Canalizing;
+    use crate::func::expr::Expr;
+    use crate::func::Formula;
+
+    #[test]
+    fn canalization() {
+        let a = Expr::ATOM(1);
+        let b = Expr::ATOM(2);
+        let c = Expr::ATOM(3);
+        let d = Expr::ATOM(4);
+
+        // Nested canalizing: a | (!b & c)
+        let f = Formula::from(a.or(&b.not().and(&c)));
+        let can = f.canalization();
+        assert_eq!(
+            can.canalizing(),
+            &[Canalizing {
+                variable: 1,
+                value: true,
+                output: true
+            }]
+        );
+        assert_eq!(can.layers.len(), 2);
+        assert_eq!(can.depth(), 3);
+        assert!(can.is_nested());
+
+        // a & (b xor c) is canalizing only in a
+        let xor = b.and(&c.not()).or(&b.not().and(&c));
+        let can = Formula::from(a.and(&xor)).canalization();
+        assert_eq!(can.depth(), 1);
+        assert_eq!(can.essential, 3);
+        assert!(!can.is_nested());
+
+        // Majority functions are not canalizing
+        let maj = a.and(&b).or(&a.and(&c)).or(&b.and(&c)).or(&d.and(&d.not()));
+        let can = Formula::from(maj).canalization();
+        assert_eq!(can.depth(), 0);
+        assert!(can.canalizing().is_empty());
+
+        // Constant functions are not canalizing
+        assert_eq!(Formula::from_bool(true).canalization().depth(), 0);
+    }
+}
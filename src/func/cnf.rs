@@ -0,0 +1,334 @@
+//! Conjunctive normal form of Boolean constraints for external SAT solvers.
+//!
+//! Expressions are converted using the Tseitin encoding: each operator is associated to an
+//! auxiliary variable constrained to be equivalent to its output, which keeps the CNF linear in
+//! the size of the expression. The resulting problem can be written in the DIMACS format
+//! and the models found by a SAT solver can be mapped back to patterns on the original variables.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::func::expr::{Expr, Operator};
+use crate::func::pattern::Pattern;
+use crate::helper::error::{EmptyLomakResult, GenericError, ParseError};
+
+/// A set of clauses over DIMACS variables (numbered from 1), some of which denote
+/// variables of the original problem while the others are auxiliary Tseitin variables.
+#[derive(Clone, Default, Debug)]
+pub struct CNF {
+    mapping: HashMap<usize, usize>,
+    uids: Vec<Option<usize>>,
+    constant: Option<usize>,
+    clauses: Vec<Vec<isize>>,
+}
+
+impl CNF {
+    pub fn new() -> Self {
+        CNF::default()
+    }
+
+    /// Number of DIMACS variables, including auxiliary ones
+    pub fn variable_count(&self) -> usize {
+        self.uids.len()
+    }
+
+    pub fn clauses(&self) -> &[Vec<isize>] {
+        &self.clauses
+    }
+
+    /// Retrieve or allocate the DIMACS literal associated to a variable
+    pub fn literal(&mut self, uid: usize, value: bool) -> isize {
+        let var = match self.mapping.get(&uid) {
+            Some(v) => *v,
+            None => {
+                let v = self.allocate(Some(uid));
+                self.mapping.insert(uid, v);
+                v
+            }
+        };
+        signed(var, value)
+    }
+
+    fn allocate(&mut self, uid: Option<usize>) -> usize {
+        self.uids.push(uid);
+        self.uids.len()
+    }
+
+    /// A literal which is always true
+    fn constant(&mut self) -> isize {
+        let var = match self.constant {
+            Some(v) => v,
+            None => {
+                let v = self.allocate(None);
+                self.constant = Some(v);
+                self.clauses.push(vec![v as isize]);
+                v
+            }
+        };
+        var as isize
+    }
+
+    pub fn add_clause(&mut self, clause: Vec<isize>) {
+        self.clauses.push(clause);
+    }
+
+    /// Add a clause excluding all states contained in a pattern
+    pub fn forbid(&mut self, p: &Pattern) {
+        let mut clause: Vec<isize> = p
+            .positive()
+            .iter()
+            .map(|u| self.literal(u, false))
+            .collect();
+        for u in p.negative().iter() {
+            clause.push(self.literal(u, true));
+        }
+        self.clauses.push(clause);
+    }
+
+    /// Add clauses enforcing a Boolean expression.
+    ///
+    /// Top-level conjunctions are split into separate constraints and top-level disjunctions
+    /// are added as a single clause, auxiliary variables are only used for nested operators.
+    pub fn require(&mut self, expr: &Expr) {
+        match expr {
+            Expr::TRUE => (),
+            Expr::OPER(Operator::AND, children) => {
+                for c in children.data.iter() {
+                    self.require(c);
+                }
+            }
+            Expr::OPER(Operator::OR, children) => {
+                let clause = children.data.iter().map(|c| self.encode(c)).collect();
+                self.clauses.push(clause);
+            }
+            _ => {
+                let lit = self.encode(expr);
+                self.clauses.push(vec![lit]);
+            }
+        }
+    }
+
+    /// Tseitin encoding: return a literal equivalent to the given expression,
+    /// adding the clauses defining the auxiliary variables if needed.
+    pub fn encode(&mut self, expr: &Expr) -> isize {
+        match expr {
+            Expr::TRUE => self.constant(),
+            Expr::FALSE => -self.constant(),
+            Expr::ATOM(u) => self.literal(*u, true),
+            Expr::NATOM(u) => self.literal(*u, false),
+            Expr::OPER(o, children) => {
+                let literals: Vec<isize> = children.data.iter().map(|c| self.encode(c)).collect();
+                let (lit, negated) = match o {
+                    Operator::AND => (self.encode_and(&literals), false),
+                    Operator::NAND => (self.encode_and(&literals), true),
+                    Operator::OR => (self.encode_or(&literals), false),
+                    Operator::NOR => (self.encode_or(&literals), true),
+                };
+                if negated {
+                    -lit
+                } else {
+                    lit
+                }
+            }
+        }
+    }
+
+    fn encode_and(&mut self, literals: &[isize]) -> isize {
+        let aux = self.allocate(None) as isize;
+        for l in literals {
+            self.clauses.push(vec![-aux, *l]);
+        }
+        let mut clause: Vec<isize> = literals.iter().map(|l| -l).collect();
+        clause.push(aux);
+        self.clauses.push(clause);
+        aux
+    }
+
+    fn encode_or(&mut self, literals: &[isize]) -> isize {
+        let aux = self.allocate(None) as isize;
+        for l in literals {
+            self.clauses.push(vec![aux, -l]);
+        }
+        let mut clause: Vec<isize> = literals.to_vec();
+        clause.push(-aux);
+        self.clauses.push(clause);
+        aux
+    }
+
+    /// Write the problem in the DIMACS format.
+    ///
+    /// Comment lines record the original variable associated to each DIMACS variable.
+    pub fn write_dimacs(&self, out: &mut dyn Write) -> EmptyLomakResult {
+        for (i, uid) in self.uids.iter().enumerate() {
+            if let Some(u) = uid {
+                writeln!(out, "c {} v{}", i + 1, u)?;
+            }
+        }
+        writeln!(out, "p cnf {} {}", self.uids.len(), self.clauses.len())?;
+        for clause in self.clauses.iter() {
+            for l in clause {
+                write!(out, "{} ", l)?;
+            }
+            writeln!(out, "0")?;
+        }
+        Ok(())
+    }
+
+    /// Parse the output of a SAT solver and extract the values of the original variables.
+    ///
+    /// This accepts the format of the SAT competition (lines starting with ``s`` and ``v``)
+    /// as well as the result files written by MiniSat (``SAT`` or ``UNSAT``, followed by a
+    /// list of literals ending with ``0``). Other lines are ignored. Returns None if the
+    /// problem is unsatisfiable and fails if the text contains no result.
+    pub fn parse_solution(&self, text: &str) -> Result<Option<Pattern>, ParseError> {
+        let mut result = Pattern::new();
+        let mut found = false;
+        for line in text.lines() {
+            let line = line.trim();
+            let literals = match line.split_whitespace().next() {
+                Some("UNSAT") => return Ok(None),
+                Some("s") if line.ends_with("UNSATISFIABLE") => return Ok(None),
+                Some("v") => &line[1..],
+                // Plain lists of literals must be complete
+                Some(_) if is_literal_list(line) => line,
+                _ => continue,
+            };
+            found = true;
+            for l in literals.split_whitespace() {
+                let l: isize = l.parse()?;
+                let var = l.unsigned_abs();
+                if var == 0 {
+                    continue;
+                }
+                match self.uids.get(var - 1) {
+                    None => {
+                        let msg = format!("Unknown SAT variable: {}", var);
+                        return Err(GenericError::new(msg).into());
+                    }
+                    Some(Some(uid)) => result.set(*uid, l > 0),
+                    Some(None) => (),
+                }
+            }
+        }
+        if !found {
+            return Err(
+                GenericError::new("No solution found in the SAT solver output".into()).into(),
+            );
+        }
+        Ok(Some(result))
+    }
+}
+
+/// Check if a line is a list of integer literals terminated by 0
+fn is_literal_list(line: &str) -> bool {
+    line.split_whitespace().last() == Some("0")
+        && line.split_whitespace().all(|l| l.parse::<isize>().is_ok())
+}
+
+fn signed(var: usize, value: bool) -> isize {
+    if value {
+        var as isize
+    } else {
+        -(var as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::cnf::CNF;
+    use crate::func::expr::Expr;
+    use crate::func::pattern::Pattern;
+    use crate::func::BoolRepr;
+
+    /// Check the satisfiability of a small CNF by enumeration
+    fn models(cnf: &CNF) -> Vec<Pattern> {
+        let n = cnf.variable_count();
+        let mut result = vec![];
+        for mask in 0..1usize << n {
+            let sat = cnf.clauses().iter().all(|c| {
+                c.iter().any(|l| {
+                    let value = mask & (1 << (l.unsigned_abs() - 1)) != 0;
+                    value == (*l > 0)
+                })
+            });
+            if sat {
+                let text: Vec<String> = (1..=n)
+                    .map(|v| {
+                        format!(
+                            "{}",
+                            if mask & (1 << (v - 1)) != 0 {
+                                v as isize
+                            } else {
+                                -(v as isize)
+                            }
+                        )
+                    })
+                    .collect();
+                let p = cnf
+                    .parse_solution(&format!("{} 0", text.join(" ")))
+                    .unwrap()
+                    .unwrap();
+                if !result.contains(&p) {
+                    result.push(p);
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn tseitin() {
+        let a = Expr::ATOM(1);
+        let b = Expr::ATOM(2);
+        let c = Expr::ATOM(3);
+
+        // (a | !b & c) & !(a & b)
+        let expr = a.or(&b.not().and(&c)).and(&a.and(&b).not());
+        let mut cnf = CNF::new();
+        cnf.require(&expr);
+        let found = models(&cnf);
+        assert_eq!(found.len(), 3);
+        for p in found.iter() {
+            let state = p.positive().iter().collect();
+            assert!(expr.eval(&state));
+        }
+
+        // Forbidden patterns and constants
+        let mut cnf = CNF::new();
+        cnf.require(&a.or(&b));
+        cnf.forbid(&Pattern::with(1, true));
+        assert_eq!(models(&cnf).len(), 1);
+        cnf.require(&Expr::FALSE);
+        assert!(models(&cnf).is_empty());
+    }
+
+    #[test]
+    fn dimacs() {
+        let mut cnf = CNF::new();
+        cnf.require(&Expr::ATOM(4).or(&Expr::NATOM(2)));
+        let mut out = vec![];
+        cnf.write_dimacs(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "c 1 v4\nc 2 v2\np cnf 2 1\n1 -2 0\n"
+        );
+
+        let p = cnf
+            .parse_solution("s SATISFIABLE\nv -1 -2\nv 0\n")
+            .unwrap()
+            .unwrap();
+        assert!(p.is_fixed_at(4, false));
+        assert!(p.is_fixed_at(2, false));
+        assert!(cnf.parse_solution("s UNSATISFIABLE\n").unwrap().is_none());
+        assert!(cnf.parse_solution("v 1 3 0").is_err());
+        assert!(cnf.parse_solution("c no result\n").is_err());
+
+        // MiniSat result files, other lines and incomplete lists are ignored
+        let p = cnf.parse_solution("SAT\n-1 2 0\n").unwrap().unwrap();
+        assert!(p.is_fixed_at(4, false));
+        assert!(p.is_fixed_at(2, true));
+        assert!(cnf.parse_solution("UNSAT\n").unwrap().is_none());
+        assert!(cnf.parse_solution("Usage: solver 1 2\n").is_err());
+        assert!(cnf.parse_solution("-1 2\n").is_err());
+    }
+}
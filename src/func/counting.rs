@@ -0,0 +1,148 @@
+//! Count the states satisfying a Boolean function and derive related statistics
+
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use crate::func::expr::Expr;
+use crate::func::implicant::Implicants;
+use crate::func::pattern::Pattern;
+use crate::func::Formula;
+use crate::helper::error::GenericError;
+
+/// Statistics on a Boolean function, computed over its essential regulators
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FunctionStats {
+    /// Number of regulators with an effect on the function
+    pub essential: usize,
+    /// Number of states of the essential regulators satisfying the function
+    pub count: u128,
+    /// Fraction of states satisfying the function
+    pub bias: f64,
+    /// Average sensitivity: sum over all regulators of the fraction of states
+    /// in which changing this regulator changes the function
+    pub sensitivity: f64,
+}
+
+impl Implicants {
+    /// Cover the same states with non-overlapping patterns
+    pub fn disjoint_cover(&self) -> Vec<Pattern> {
+        let mut disjoint: Vec<Pattern> = vec![];
+        for p in self.iter() {
+            let mut fragments = vec![p.clone()];
+            for q in disjoint.iter() {
+                fragments = fragments.iter().flat_map(|f| f.difference(q)).collect();
+                if fragments.is_empty() {
+                    break;
+                }
+            }
+            disjoint.extend(fragments);
+        }
+        disjoint
+    }
+
+    /// Fraction of the state space satisfying the function
+    pub fn bias(&self) -> f64 {
+        self.disjoint_cover()
+            .iter()
+            .map(|p| 0.5f64.powi(p.len() as i32))
+            .sum()
+    }
+
+    /// Number of states satisfying the function among the states of the given number of
+    /// variables, which should include all regulators.
+    ///
+    /// Fails if the number of states does not fit in 128 bits.
+    pub fn count_states(&self, variables: usize) -> Result<u128, GenericError> {
+        let overflow = || GenericError::new(format!("Too many states for {} variables", variables));
+        let mut count: u128 = 0;
+        for p in self.disjoint_cover() {
+            let free = u32::try_from(variables - p.len()).map_err(|_| overflow())?;
+            let states = 1u128.checked_shl(free).ok_or_else(overflow)?;
+            count = count.checked_add(states).ok_or_else(overflow)?;
+        }
+        Ok(count)
+    }
+}
+
+impl Formula {
+    /// Compute statistics on this function, fails if it has too many regulators
+    pub fn stats(&self) -> Result<FunctionStats, GenericError> {
+        let expr: Rc<Expr> = self.convert_as();
+        let primes: Rc<Implicants> = self.convert_as();
+        let regulators: Vec<usize> = self
+            .regulator_effects()
+            .iter()
+            .filter(|(_, e)| e.is_functional())
+            .map(|(u, _)| *u)
+            .collect();
+
+        let mut sensitivity = 0.0;
+        for uid in regulators.iter() {
+            let f1 = restrict(&expr, *uid, true);
+            let f0 = restrict(&expr, *uid, false);
+            let diff = f1.and(&f0.not()).or(&f1.not().and(&f0));
+            sensitivity += diff.prime_implicants().bias();
+        }
+
+        Ok(FunctionStats {
+            essential: regulators.len(),
+            count: primes.count_states(regulators.len())?,
+            bias: primes.bias(),
+            sensitivity,
+        })
+    }
+}
+
+fn restrict(expr: &Expr, uid: usize, value: bool) -> Expr {
+    match expr.replace_variables(&mut Pattern::with(uid, value)) {
+        None => expr.clone(),
+        Some(e) => e.simplify().unwrap_or(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::func::expr::Expr;
+    use crate::func::implicant::Implicants;
+    use crate::func::Formula;
+
+    #[test]
+    fn function_stats() {
+        let a = Expr::ATOM(1);
+        let b = Expr::ATOM(2);
+        let c = Expr::ATOM(3);
+
+        // a | b & c: 5 states out of 8, influences 3/4, 1/4, 1/4
+        let stats = Formula::from(a.or(&b.and(&c))).stats().unwrap();
+        assert_eq!(stats.essential, 3);
+        assert_eq!(stats.count, 5);
+        assert_eq!(stats.bias, 0.625);
+        assert_eq!(stats.sensitivity, 1.25);
+
+        // xor: fully sensitive
+        let stats = Formula::from(a.and(&b.not()).or(&a.not().and(&b)))
+            .stats()
+            .unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.sensitivity, 2.0);
+
+        let stats = Formula::from_bool(true).stats().unwrap();
+        assert_eq!(stats.essential, 0);
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.sensitivity, 0.0);
+        assert_eq!(Formula::from_bool(false).stats().unwrap().bias, 0.0);
+    }
+
+    #[test]
+    fn count_overflow() {
+        let primes: Rc<Implicants> = Formula::from(Expr::ATOM(1)).convert_as();
+        assert_eq!(primes.count_states(128).unwrap(), 1 << 127);
+        assert!(primes.count_states(129).is_err());
+
+        let primes: Rc<Implicants> = Formula::from_bool(true).convert_as();
+        assert_eq!(primes.count_states(127).unwrap(), 1 << 127);
+        assert!(primes.count_states(128).is_err());
+    }
+}
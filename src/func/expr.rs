@@ -149,6 +149,7 @@ impl FromBoolRepr for Expr {
         match repr {
             Repr::EXPR(e) => e.clone(),
             Repr::GEN(g) => Rc::new(g.to_expr()),
+            Repr::THRESHOLD(t) => Rc::new(t.to_expr()),
             Repr::PRIMES(p) => Rc::new(p.to_expr()),
         }
     }
@@ -472,14 +473,10 @@ impl Children {
         let children: Vec<Option<Expr>> = self.data.iter().map(|c| c.replace_literal(f)).collect();
         let count = children.iter().filter(|c| c.is_some()).count();
         if count > 1 {
-            let children = self
-                .data
-                .iter()
-                .zip(children.into_iter())
-                .map(|(c, r)| match r {
-                    Some(e) => e,
-                    None => c.clone(),
-                });
+            let children = self.data.iter().zip(children).map(|(c, r)| match r {
+                Some(e) => e,
+                None => c.clone(),
+            });
             return Some(Expr::OPER(
                 op,
                 Children {
@@ -523,17 +520,21 @@ impl<'a> FormatContext<'a> {
         }
     }
 
-    fn from_group(grp: &dyn VariableNamer) -> FormatContext {
+    fn from_group(grp: &dyn VariableNamer) -> FormatContext<'_> {
         FormatContext {
             parent_priority: 0,
             group: Some(grp),
         }
     }
 
-    fn update_priority(&mut self, op: Operator) -> bool {
+    /// Enter an operator, returns the previous priority and true if parenthesis are needed.
+    ///
+    /// The top-level operator and operators with a lower priority than their parent
+    /// are enclosed in parenthesis.
+    fn enter_operator(&mut self, op: Operator) -> (u8, bool) {
         let old = self.parent_priority;
         self.parent_priority = op.priority();
-        self.parent_priority > old
+        (old, old == 0 || self.parent_priority < old)
     }
 
     fn write_var(&self, f: &mut fmt::Formatter, uid: usize) -> fmt::Result {
@@ -613,7 +614,7 @@ impl Children {
             return write!(f, "[]");
         }
 
-        let need_paren = context.update_priority(op);
+        let (parent_priority, need_paren) = context.enter_operator(op);
 
         let mut prefix = "";
         let mut postfix = "";
@@ -629,12 +630,11 @@ impl Children {
         n -= 1;
         for idx in 0..n {
             let r = self.data[idx]._fmt(f, context);
-            if r.is_err() {
-                return r;
-            }
+            r?;
             write!(f, " {} ", op)?;
         }
         self.data[n]._fmt(f, context)?;
+        context.parent_priority = parent_priority;
         write!(f, "{}", postfix)
     }
 }
@@ -649,7 +649,7 @@ impl Not for Expr {
         Expr::not(&self)
     }
 }
-impl<'a> Not for &'a Expr {
+impl Not for &Expr {
     type Output = Expr;
     fn not(self) -> Expr {
         Expr::not(self)
@@ -663,14 +663,14 @@ impl BitAnd for Expr {
     }
 }
 
-impl<'a> BitAnd<&'a Expr> for Expr {
+impl BitAnd<&Expr> for Expr {
     type Output = Self;
     fn bitand(self, rhs: &Self) -> Self {
         self.and(rhs)
     }
 }
 
-impl<'a> BitAnd<Expr> for &'a Expr {
+impl BitAnd<Expr> for &Expr {
     type Output = Expr;
     fn bitand(self, rhs: Expr) -> Expr {
         self.and(&rhs)
@@ -691,14 +691,14 @@ impl BitOr for Expr {
     }
 }
 
-impl<'a> BitOr<&'a Expr> for Expr {
+impl BitOr<&Expr> for Expr {
     type Output = Self;
     fn bitor(self, rhs: &Self) -> Self {
         self.or(rhs)
     }
 }
 
-impl<'a> BitOr<Expr> for &'a Expr {
+impl BitOr<Expr> for &Expr {
     type Output = Expr;
     fn bitor(self, rhs: Expr) -> Expr {
         self.or(&rhs)
@@ -722,7 +722,7 @@ mod tests {
 
     #[test]
     fn conj_extension() {
-        let grp = TrivialNamer {};
+        let _grp = TrivialNamer {};
         let a = Expr::ATOM(1);
         let b = Expr::ATOM(2);
         let c = Expr::ATOM(3);
@@ -752,6 +752,21 @@ mod tests {
         assert_eq!(c_expr.nnf().unwrap(), n_expr);
     }
 
+    #[test]
+    fn display() {
+        let a = Expr::ATOM(1);
+        let b = Expr::ATOM(2);
+        let c = Expr::ATOM(3);
+
+        assert_eq!(format!("{}", a.or(&b).and(&c)), "((v1 | v2) & v3)");
+        assert_eq!(format!("{}", a.and(&b).or(&c)), "(v1 & v2 | v3)");
+        assert_eq!(
+            format!("{}", c.and(&a.or(&b)).or(&a)),
+            "(v3 & (v1 | v2) | v1)"
+        );
+        assert_eq!(format!("{}", a.or(&b).not().and(&c)), "(!(v1 | v2) & v3)");
+    }
+
     #[test]
     fn evaluation() {
         let a = Expr::ATOM(1);
@@ -762,16 +777,16 @@ mod tests {
         let path: Rc<Implicants> = expr.clone().into_repr().convert_as();
 
         let mut state = State::new();
-        assert_eq!(expr.eval(&state), true);
-        assert_eq!(path.eval(&state), true);
+        assert!(expr.eval(&state));
+        assert!(path.eval(&state));
         state.insert(2);
-        assert_eq!(expr.eval(&state), false);
-        assert_eq!(path.eval(&state), false);
+        assert!(!expr.eval(&state));
+        assert!(!path.eval(&state));
         state.insert(3);
-        assert_eq!(expr.eval(&state), false);
-        assert_eq!(path.eval(&state), false);
+        assert!(!expr.eval(&state));
+        assert!(!path.eval(&state));
         state.insert(1);
-        assert_eq!(expr.eval(&state), true);
-        assert_eq!(path.eval(&state), true);
+        assert!(expr.eval(&state));
+        assert!(path.eval(&state));
     }
 }
@@ -9,6 +9,12 @@ impl Expr {
         paths
     }
 
+    /// Check if two expressions denote the same Boolean function
+    pub fn is_equivalent(&self, other: &Expr) -> bool {
+        let xor = self.and(&other.not()).or(&other.and(&self.not()));
+        xor.prime_implicants().is_empty()
+    }
+
     /// Construct prime implicants.
     ///
     /// This method handles terminal nodes (booleans and atoms) and relies on
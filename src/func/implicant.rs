@@ -43,7 +43,7 @@ impl Implicants {
     /// Retrieve all literals (including their negation) from this list of implicants.
     /// The result is represented as a pattern, which can contain conflicts if the same variables is
     /// fixed at different values in different implicants.
-    fn get_literals(&self) -> Pattern {
+    pub fn get_literals(&self) -> Pattern {
         let mut lits = Pattern::new();
         for l in self.patterns.iter() {
             lits.add_constraints_from(l);
@@ -133,7 +133,7 @@ impl Implicants {
                     continue;
                 }
 
-                match b.relate(&t) {
+                match b.relate(t) {
                     PatternRelation::Disjoint => {}
                     PatternRelation::Overlap => {}
                     PatternRelation::Contains => {
@@ -226,7 +226,7 @@ impl Implicants {
 
     /// Remove all paths contained in another list of implicants
     pub fn substract(&mut self, other: &Implicants) {
-        self.patterns.retain(|b| !other.contains_path(&b));
+        self.patterns.retain(|b| !other.contains_path(b));
     }
 
     fn contains_path(&self, path: &Pattern) -> bool {
@@ -282,7 +282,7 @@ impl Implicants {
     pub fn to_expr(&self) -> Expr {
         let mut expr = Expr::FALSE;
         for p in self.patterns.iter() {
-            expr = expr.and(&p.to_expr());
+            expr = expr.or(&p.to_expr());
         }
         expr
     }
@@ -346,6 +346,7 @@ impl FromBoolRepr for Implicants {
         match repr {
             Repr::PRIMES(p) => p.clone(),
             Repr::GEN(g) => Rc::new(g.to_expr().prime_implicants()),
+            Repr::THRESHOLD(t) => Rc::new(t.to_expr().prime_implicants()),
             Repr::EXPR(e) => Rc::new(e.prime_implicants()),
         }
     }
@@ -362,18 +363,19 @@ impl FromBoolRepr for Implicants {
 #[cfg(test)]
 mod tests {
     use crate::func::implicant::*;
+    use crate::func::state::State;
     use std::str::FromStr;
 
     #[test]
     fn test_implicants() {
         let a = Pattern::from_str("--0-1--00-").unwrap();
-        let b = Pattern::from_str("0-0-11-00-").unwrap();
-        let c = Pattern::from_str("0-1-11-00-").unwrap();
+        let _b = Pattern::from_str("0-0-11-00-").unwrap();
+        let _c = Pattern::from_str("0-1-11-00-").unwrap();
 
         let implicants = Implicants::new();
 
-        assert_eq!(implicants.eval_in_pattern(&a), true);
-        assert_eq!(implicants.covers_pattern(&a), true);
+        assert!(implicants.eval_in_pattern(&a));
+        assert!(implicants.covers_pattern(&a));
     }
 
     #[test]
@@ -386,9 +388,32 @@ mod tests {
             .and(&v2)
             .and(&v3)
             .or(&v1.not().and(&v2.not()).and(&v3.not()));
-        let pi = expr.prime_implicants();
+        let _pi = expr.prime_implicants();
 
         let nexpr = expr.not();
-        let npi = nexpr.prime_implicants();
+        let _npi = nexpr.prime_implicants();
+    }
+
+    #[test]
+    fn implicants_to_expr() {
+        // The negative literals of a pattern are kept
+        let p = Pattern::from_str("10-").unwrap().to_expr();
+        let mut state = State::new();
+        state.insert(0);
+        assert!(p.eval(&state));
+        state.insert(1);
+        assert!(!p.eval(&state));
+
+        let expr = Expr::ATOM(1).and(&Expr::NATOM(2)).or(&Expr::ATOM(3));
+        let converted = expr.prime_implicants().to_expr();
+        for bits in 0..8 {
+            let mut state = State::new();
+            for uid in 1..=3 {
+                if bits & (1 << (uid - 1)) != 0 {
+                    state.insert(uid);
+                }
+            }
+            assert_eq!(converted.eval(&state), expr.eval(&state));
+        }
     }
 }
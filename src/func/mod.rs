@@ -9,13 +9,21 @@ use crate::func::state::State;
 use self::expr::Expr;
 use self::gen::Generator;
 use self::implicant::Implicants;
+use self::threshold::ThresholdFunction;
 
+pub mod canalization;
+pub mod cnf;
+pub mod counting;
 pub mod expr;
 pub mod expr2primes;
 pub mod gen;
 pub mod implicant;
+pub mod monotonicity;
 pub mod pattern;
 pub mod state;
+pub mod str2expr;
+pub mod str2pattern;
+pub mod threshold;
 
 pub trait VariableNamer {
     /// Write the name corresponding to the given UID
@@ -72,6 +80,7 @@ impl VariableNamer for TrivialNamer {
 pub enum Repr {
     EXPR(Rc<Expr>),
     GEN(Rc<Generator>),
+    THRESHOLD(Rc<ThresholdFunction>),
     PRIMES(Rc<Implicants>),
 }
 
@@ -99,6 +108,7 @@ impl BoolRepr for Repr {
             Repr::EXPR(e) => e.eval(state),
             Repr::PRIMES(p) => p.eval(state),
             Repr::GEN(g) => g.eval(state),
+            Repr::THRESHOLD(t) => t.eval(state),
         }
     }
 }
@@ -183,6 +193,7 @@ impl Grouped for Formula {
         match &self.repr {
             Repr::EXPR(e) => e.gfmt(namer, f),
             Repr::GEN(g) => g.gfmt(namer, f),
+            Repr::THRESHOLD(t) => t.gfmt(namer, f),
             Repr::PRIMES(p) => write!(f, "{}", p),
         }
     }
@@ -193,6 +204,7 @@ impl fmt::Display for Repr {
         match &self {
             Repr::EXPR(e) => write!(f, "{}", e),
             Repr::GEN(g) => write!(f, "{}", g),
+            Repr::THRESHOLD(t) => write!(f, "{}", t),
             Repr::PRIMES(p) => write!(f, "{}", p),
         }
     }
@@ -0,0 +1,131 @@
+//! Identify the effect (sign) of the regulators of a Boolean function
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::func::expr::Expr;
+use crate::func::implicant::Implicants;
+use crate::func::pattern::Pattern;
+use crate::func::Formula;
+
+/// Effect of a regulator on a Boolean function
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Effect {
+    /// The function is positively monotone in this regulator
+    POSITIVE,
+    /// The function is negatively monotone in this regulator
+    NEGATIVE,
+    /// The regulator has both positive and negative effects
+    DUAL,
+    /// The function does not depend on this regulator
+    NONE,
+}
+
+impl Effect {
+    /// Extract the effect of a variable from a set of literals, which may contain conflicts
+    pub fn from_literals(literals: &Pattern, uid: usize) -> Self {
+        match (
+            literals.is_fixed_at(uid, true),
+            literals.is_fixed_at(uid, false),
+        ) {
+            (true, true) => Effect::DUAL,
+            (true, false) => Effect::POSITIVE,
+            (false, true) => Effect::NEGATIVE,
+            (false, false) => Effect::NONE,
+        }
+    }
+
+    /// Combine the effects of the same regulator on several functions
+    pub fn merge(self, other: Effect) -> Self {
+        match (self, other) {
+            (Effect::NONE, e) => e,
+            (e, Effect::NONE) => e,
+            (a, b) if a == b => a,
+            _ => Effect::DUAL,
+        }
+    }
+
+    pub fn is_functional(self) -> bool {
+        self != Effect::NONE
+    }
+}
+
+impl Implicants {
+    /// Identify the effect of all regulators found in this list of implicants.
+    ///
+    /// As the list contains all prime implicants of the function, a regulator is an activator
+    /// if it is only found as a positive literal, an inhibitor if it is only found as a negative
+    /// literal, and dual if it is found with both signs.
+    pub fn effects(&self) -> BTreeMap<usize, Effect> {
+        let literals = self.get_literals();
+        self.get_regulators()
+            .iter()
+            .map(|uid| (uid, Effect::from_literals(&literals, uid)))
+            .collect()
+    }
+}
+
+impl Formula {
+    /// Identify the effect of all regulators of this function.
+    ///
+    /// Variables which are used in the function without affecting its value are
+    /// associated to the ```NONE``` effect.
+    pub fn regulator_effects(&self) -> BTreeMap<usize, Effect> {
+        let expr: Rc<Expr> = self.convert_as();
+        let primes: Rc<Implicants> = self.convert_as();
+        let mut effects = primes.effects();
+
+        let literals = expr.get_literals();
+        for uid in literals.positive().union(literals.negative()) {
+            effects.entry(uid).or_insert(Effect::NONE);
+        }
+        effects
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Effect::POSITIVE => write!(f, "+"),
+            Effect::NEGATIVE => write!(f, "-"),
+            Effect::DUAL => write!(f, "+-"),
+            Effect::NONE => write!(f, "0"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::func::implicant::Implicants;
+    use crate::func::monotonicity::Effect;
+    use crate::func::Formula;
+
+    #[test]
+    fn regulator_effects() {
+        let a = Expr::ATOM(1);
+        let b = Expr::ATOM(2);
+        let c = Expr::ATOM(3);
+        let d = Expr::ATOM(4);
+
+        // a & !b | (a ^ c) | d & !d
+        let xor = a.and(&c.not()).or(&a.not().and(&c));
+        let expr = a.and(&b.not()).or(&xor).or(&d.and(&d.not()));
+        let effects = Formula::from(expr.clone()).regulator_effects();
+        assert_eq!(effects.get(&1), Some(&Effect::DUAL));
+        assert_eq!(effects.get(&2), Some(&Effect::NEGATIVE));
+        assert_eq!(effects.get(&3), Some(&Effect::DUAL));
+        assert_eq!(effects.get(&4), Some(&Effect::NONE));
+
+        // The same effects are found from the prime implicants
+        let primes = Formula::from(expr.prime_implicants());
+        assert_eq!(primes.regulator_effects().get(&4), None);
+        assert_eq!(primes.regulator_effects().get(&2), Some(&Effect::NEGATIVE));
+
+        let effects = Implicants::new().effects();
+        assert!(effects.is_empty());
+        assert_eq!(Effect::POSITIVE.merge(Effect::NONE), Effect::POSITIVE);
+        assert_eq!(Effect::POSITIVE.merge(Effect::NEGATIVE), Effect::DUAL);
+    }
+}
@@ -3,7 +3,7 @@ use std::vec::Vec;
 
 use bit_set::BitSet;
 
-use crate::func::expr::Expr;
+use crate::func::expr::{AtomReplacer, Expr};
 use crate::func::state::State;
 use crate::func::VariableNamer;
 use crate::helper::error::ParseError;
@@ -211,21 +211,123 @@ impl Pattern {
             _ => PatternRelation::Disjoint,
         }
     }
+
+    /// Compute the intersection of two patterns.
+    ///
+    /// Returns None if the patterns have conflicting restrictions, i.e. if they do not
+    /// share any state.
+    pub fn intersect(&self, other: &Pattern) -> Option<Pattern> {
+        if !self.conflicts(other).is_empty() {
+            return None;
+        }
+        let mut result = self.clone();
+        result.add_constraints_from(other);
+        Some(result)
+    }
+
+    /// Compute the smallest pattern containing all given patterns.
+    ///
+    /// It retains the variables fixed at the same value in all patterns.
+    /// Returns None if the list of patterns is empty.
+    pub fn hull<'a>(patterns: impl IntoIterator<Item = &'a Pattern>) -> Option<Pattern> {
+        let mut iter = patterns.into_iter();
+        let mut result = iter.next()?.clone();
+        for p in iter {
+            result = result.get_common_restrictions(p);
+        }
+        Some(result)
+    }
+
+    /// Compute the states of this pattern which are not in another pattern.
+    ///
+    /// The difference is given as a list of disjoint patterns, which is empty if
+    /// this pattern is included in the other one.
+    pub fn difference(&self, other: &Pattern) -> Vec<Pattern> {
+        if !self.conflicts(other).is_empty() {
+            return vec![self.clone()];
+        }
+
+        // Free each constraint of the other pattern in turn: fix it to the
+        // opposite value to obtain a new fragment, then to the same value to continue
+        let mut result = vec![];
+        let mut cur = self.clone();
+        let literals = other
+            .positive
+            .iter()
+            .map(|u| (u, true))
+            .chain(other.negative.iter().map(|u| (u, false)));
+        for (uid, value) in literals {
+            if !cur.is_fixed(uid) {
+                let mut r = cur.clone();
+                r.set(uid, !value);
+                result.push(r);
+                cur.set(uid, value);
+            }
+        }
+        result
+    }
+
+    /// Compute the complement of this pattern as a list of disjoint patterns.
+    pub fn complement(&self) -> Vec<Pattern> {
+        Pattern::new().difference(self)
+    }
+
+    /// Enumerate the states covered by this pattern over a list of variables.
+    ///
+    /// Restrictions on variables outside of this list are ignored.
+    pub fn states(&self, variables: &[usize]) -> PatternStates {
+        let base: State = variables
+            .iter()
+            .filter(|u| self.positive.contains(**u))
+            .copied()
+            .collect();
+        let free = variables
+            .iter()
+            .filter(|u| !self.is_fixed(**u))
+            .copied()
+            .collect();
+        PatternStates {
+            free,
+            next: Some(base),
+        }
+    }
 }
 
+/// Iterator over the states covered by a pattern, see [`Pattern::states`]
+pub struct PatternStates {
+    free: Vec<usize>,
+    next: Option<State>,
+}
+
+impl Iterator for PatternStates {
+    type Item = State;
+
+    fn next(&mut self) -> Option<State> {
+        let current = self.next.take()?;
+
+        // Increment the free variables like a binary counter
+        let mut next = current.clone();
+        for uid in self.free.iter() {
+            if next.remove(*uid) {
+                continue;
+            }
+            next.insert(*uid);
+            self.next = Some(next);
+            break;
+        }
+        Some(current)
+    }
+}
+
+/// Parse a ternary string where the position of each character gives the variable uid.
+///
+/// Use [`Pattern::parse`] to follow the order of variables in a model.
 impl FromStr for Pattern {
     type Err = ParseError;
 
     fn from_str(descr: &str) -> Result<Pattern, ParseError> {
-        let mut l = Pattern::new();
-        for (idx, c) in descr.chars().enumerate() {
-            match c {
-                '0' => l.set(idx, false),
-                '1' => l.set(idx, true),
-                _ => (), // TODO: return error
-            }
-        }
-        Ok(l)
+        let variables: Vec<usize> = (0..descr.chars().count()).collect();
+        Pattern::parse_ternary(descr, &variables)
     }
 }
 
@@ -310,7 +412,7 @@ impl Pattern {
         for uid in self.positive.iter() {
             expr = expr.and(&Expr::ATOM(uid))
         }
-        for uid in self.positive.iter() {
+        for uid in self.negative.iter() {
             expr = expr.and(&Expr::NATOM(uid))
         }
         expr
@@ -356,6 +458,20 @@ impl Pattern {
     }
 }
 
+/// Restrict functions to the subspace defined by a pattern:
+/// all fixed variables are replaced by the corresponding constant.
+impl AtomReplacer for Pattern {
+    fn replace(&mut self, var: usize, value: bool) -> Option<Expr> {
+        if self.positive.contains(var) {
+            Some(Expr::from_bool(value))
+        } else if self.negative.contains(var) {
+            Some(Expr::from_bool(!value))
+        } else {
+            None
+        }
+    }
+}
+
 impl fmt::Display for Pattern {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut result = vec![];
@@ -398,7 +514,8 @@ impl Pattern {
 #[cfg(test)]
 mod tests {
     use crate::func::pattern::Pattern;
-    use crate::func::pattern::PatternRelation::{Disjoint, JoinBoth, JoinFirst};
+    use crate::func::pattern::PatternRelation::{JoinBoth, JoinFirst};
+    use std::collections::HashSet;
     use std::str::FromStr;
 
     #[test]
@@ -417,18 +534,72 @@ mod tests {
         assert_eq!(c.len(), 6);
         assert_eq!(c.positive().len(), 3);
 
-        assert_eq!(a.contains(&b), true);
-        assert_eq!(a.contains(&c), false);
+        assert!(a.contains(&b));
+        assert!(!a.contains(&c));
 
-        assert_eq!(b.contains(&a), false);
-        assert_eq!(c.contains(&a), false);
+        assert!(!b.contains(&a));
+        assert!(!c.contains(&a));
 
-        assert_eq!(mbc.contains(&a), false);
-        assert_eq!(mbc.contains(&b), true);
-        assert_eq!(mbc.contains(&c), true);
-        assert_eq!(mbc.contains(&p), false);
+        assert!(!mbc.contains(&a));
+        assert!(mbc.contains(&b));
+        assert!(mbc.contains(&c));
+        assert!(!mbc.contains(&p));
 
         assert_eq!(p.relate(&a), JoinFirst(mpa));
         assert_eq!(b.relate(&c), JoinBoth(mbc));
     }
+
+    /// Collect the states covered by a list of patterns over 4 variables
+    fn covered<'a>(patterns: impl IntoIterator<Item = &'a Pattern>) -> HashSet<Vec<usize>> {
+        patterns
+            .into_iter()
+            .flat_map(|p| p.states(&[0, 1, 2, 3]))
+            .map(|s| s.iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn pattern_algebra() {
+        let a = Pattern::from_str("1--0").unwrap();
+        let b = Pattern::from_str("-0-0").unwrap();
+        let c = Pattern::from_str("0---").unwrap();
+
+        assert_eq!(a.states(&[0, 1, 2, 3]).count(), 4);
+        assert_eq!(Pattern::new().states(&[0, 1, 2, 3]).count(), 16);
+        assert_eq!(a.states(&[0, 3]).count(), 1);
+
+        // Intersection
+        assert_eq!(a.intersect(&b), Pattern::from_str("10-0").ok());
+        assert_eq!(a.intersect(&c), None);
+        let expected: HashSet<_> = covered(Some(&a))
+            .intersection(&covered(Some(&b)))
+            .cloned()
+            .collect();
+        assert_eq!(covered(&a.intersect(&b)), expected);
+
+        // Hull
+        assert_eq!(
+            Pattern::hull(&[a.clone(), b.clone()]),
+            Pattern::from_str("---0").ok()
+        );
+        assert_eq!(Pattern::hull(&[a.clone(), c.clone()]), Some(Pattern::new()));
+        assert_eq!(Pattern::hull(&[]), None);
+
+        // Difference and complement, as disjoint patterns
+        let diff = a.difference(&b);
+        let expected: HashSet<_> = covered(Some(&a))
+            .difference(&covered(Some(&b)))
+            .cloned()
+            .collect();
+        assert_eq!(covered(&diff), expected);
+        let total: usize = diff.iter().map(|p| p.states(&[0, 1, 2, 3]).count()).sum();
+        assert_eq!(total, expected.len());
+        assert!(a.difference(&Pattern::from_str("---0").unwrap()).is_empty());
+        assert_eq!(a.difference(&c), vec![a.clone()]);
+
+        let complement = a.complement();
+        assert_eq!(complement.len(), 2);
+        assert!(covered(&complement).is_disjoint(&covered(Some(&a))));
+        assert_eq!(covered(&complement).len(), 12);
+    }
 }
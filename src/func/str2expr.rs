@@ -0,0 +1,162 @@
+//! Parse logical expressions from strings, using a group of variables to resolve names.
+//!
+//! This relies on the expression grammar of the mnet format, which is loaded with the same functions.
+
+use pest::iterators::Pair;
+use pest::Parser;
+
+use crate::func::expr::{Comparator, Expr, Operator};
+use crate::helper::error::ParseError;
+use crate::model::io::mnet::{MNETParser, Rule};
+use crate::variables::GroupedVariables;
+
+impl Expr {
+    /// Parse an expression and bind its literals to a group of variables.
+    ///
+    /// Literals can denote a component (``A``), a specific threshold (``A:2``)
+    /// or a comparison between the level of a component and a value (``A >= 2``).
+    /// Missing components and thresholds are created in the group.
+    pub fn parse(text: &str, grp: &mut impl GroupedVariables) -> Result<Expr, ParseError> {
+        let mut ptree = MNETParser::parse(Rule::sxpr, text)?;
+        let expr = ptree.next().unwrap().into_inner().next().unwrap();
+        load_expr(grp, expr)
+    }
+}
+
+/// Load a parsed expression, creating the missing components and thresholds
+pub(crate) fn load_expr(
+    grp: &mut impl GroupedVariables,
+    expr: Pair<Rule>,
+) -> Result<Expr, ParseError> {
+    let rtype = expr.as_rule();
+    match rtype {
+        Rule::bt => Ok(Expr::TRUE),
+        Rule::bf => Ok(Expr::FALSE),
+        Rule::lit => Ok(Expr::ATOM(load_variable(grp, expr)?)),
+        Rule::cmp => load_cmp(grp, expr),
+        _ => {
+            let mut content = vec![];
+            for e in expr.into_inner() {
+                content.push(load_expr(grp, e)?);
+            }
+            match rtype {
+                Rule::conj => Ok(Operator::AND.join(&mut content.into_iter())),
+                Rule::disj => Ok(Operator::OR.join(&mut content.into_iter())),
+                Rule::neg => Ok(content[0].not()),
+                // Other rules are outside of scope or hidden
+                _ => panic!("Parsing tokens should not get there"),
+            }
+        }
+    }
+}
+
+/// Load a parsed literal (``A`` or ``A:2``) and return the corresponding variable
+pub(crate) fn load_variable(
+    grp: &mut impl GroupedVariables,
+    expr: Pair<Rule>,
+) -> Result<usize, ParseError> {
+    let mut inner = expr.into_inner();
+    let name = inner.next().unwrap().as_str();
+    let cid = grp.ensure(name);
+    match inner.next() {
+        None => Ok(cid),
+        Some(v) => {
+            let value = v.as_str().parse()?;
+            if value < 1 {
+                return Err(ParseError::InvalidValue(name.to_owned(), value));
+            }
+            Ok(grp.ensure_threshold(cid, value)?)
+        }
+    }
+}
+
+fn load_cmp(grp: &mut impl GroupedVariables, expr: Pair<Rule>) -> Result<Expr, ParseError> {
+    let mut inner = expr.into_inner();
+    let name = inner.next().unwrap().as_str();
+    let cmp = match inner.next().unwrap().as_str() {
+        "=" | "==" => Comparator::EQ,
+        "!=" => Comparator::NEQ,
+        ">" => Comparator::GT,
+        ">=" => Comparator::GEQ,
+        "<" => Comparator::LT,
+        "<=" => Comparator::LEQ,
+        _ => panic!("Parsing tokens should not get there"),
+    };
+    let value = inner.next().unwrap().as_str().parse()?;
+
+    let cid = grp.ensure(name);
+    if value > 0 {
+        grp.ensure_threshold(cid, value)?;
+    }
+    cmp.get_expr(grp, cid, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::helper::error::ParseError;
+    use crate::variables::{GroupedVariables, ModelVariables};
+
+    #[test]
+    fn parse_boolean() {
+        let mut grp = ModelVariables::default();
+        let e = Expr::parse("A & !(B | C) | true", &mut grp).unwrap();
+        let a = grp.get_handle("A").unwrap();
+        let b = grp.get_handle("B").unwrap();
+        let c = grp.get_handle("C").unwrap();
+
+        let expected = Expr::ATOM(a)
+            .and(&Expr::ATOM(b).or(&Expr::ATOM(c)).not())
+            .or(&Expr::TRUE);
+        assert_eq!(e, expected);
+    }
+
+    #[test]
+    fn parse_multivalued() {
+        let mut grp = ModelVariables::default();
+        let e = Expr::parse("A:2 & B", &mut grp).unwrap();
+        let a = grp.get_handle("A").unwrap();
+        let a2 = grp.get_variable(a, 2).unwrap();
+        let b = grp.get_handle("B").unwrap();
+        assert_eq!(grp.get_variables(a).len(), 2);
+        assert_eq!(e, Expr::ATOM(a2).and(&Expr::ATOM(b)));
+
+        // Comparisons rely on the existing thresholds
+        assert_eq!(Expr::parse("A >= 2", &mut grp).unwrap(), Expr::ATOM(a2));
+        assert_eq!(Expr::parse("A < 2", &mut grp).unwrap(), Expr::NATOM(a2));
+        assert_eq!(
+            Expr::parse("A = 1", &mut grp).unwrap(),
+            Expr::ATOM(a).and(&Expr::NATOM(a2))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut grp = ModelVariables::default();
+        assert!(matches!(
+            Expr::parse("A &", &mut grp),
+            Err(ParseError::ParseText(_))
+        ));
+        assert!(matches!(
+            Expr::parse("A:0", &mut grp),
+            Err(ParseError::InvalidValue(_, 0))
+        ));
+        assert!(matches!(
+            Expr::parse("A > 99999999999999999999999", &mut grp),
+            Err(ParseError::ParseInt(_))
+        ));
+
+        // Levels above the maximal level are rejected without creating thresholds
+        assert!(Expr::parse("A > 99999999", &mut grp).is_err());
+        assert!(Expr::parse("A:100000000", &mut grp).is_err());
+        let a = grp.get_handle("A").unwrap();
+        assert_eq!(grp.get_variables(a).len(), 1);
+
+        // Components are not limited to a few levels
+        let e = Expr::parse("A:12 | A >= 15", &mut grp).unwrap();
+        assert_eq!(grp.get_variables(a).len(), 15);
+        assert_eq!(grp.get_handle("A:12"), grp.get_variable(a, 12));
+        let literals = e.get_literals();
+        assert!(literals.is_fixed_at(grp.get_variable(a, 12).unwrap(), true));
+    }
+}
@@ -0,0 +1,184 @@
+//! Parse patterns from strings, using a group of variables to resolve names or positions
+//!
+//! Two syntaxes are supported:
+//! * ternary strings (``-01-``), where each character gives the value of the corresponding variable
+//! * named restrictions (``A=1,B:2=0`` or ``A,!B``), where unrestricted variables are omitted
+
+use crate::func::pattern::Pattern;
+use crate::helper::error::{GenericError, ParseError};
+use crate::variables::GroupedVariables;
+
+fn parse_error(msg: String) -> ParseError {
+    GenericError::new(msg).into()
+}
+
+impl Pattern {
+    /// Parse a pattern, detecting the syntax.
+    ///
+    /// Strings made only of ``0``, ``1`` and ``-`` characters use the ternary syntax and
+    /// must match the order of the variables in the group, other strings use the named syntax.
+    pub fn parse(descr: &str, grp: &impl GroupedVariables) -> Result<Pattern, ParseError> {
+        let descr = descr.trim();
+        if !descr.is_empty() && descr.chars().all(|c| c == '0' || c == '1' || c == '-') {
+            let variables: Vec<usize> = grp.variables().copied().collect();
+            return Pattern::parse_ternary(descr, &variables);
+        }
+        Pattern::parse_named(descr, grp)
+    }
+
+    /// Parse several patterns and combine their restrictions.
+    ///
+    /// Patterns with conflicting restrictions are rejected.
+    pub fn parse_all(
+        descrs: &[String],
+        grp: &impl GroupedVariables,
+    ) -> Result<Pattern, ParseError> {
+        let mut result = Pattern::new();
+        for descr in descrs {
+            let p = Pattern::parse(descr, grp)?;
+            result = result.intersect(&p).ok_or_else(|| {
+                parse_error(format!(
+                    "Pattern '{}' conflicts with previous restrictions",
+                    descr
+                ))
+            })?;
+        }
+        Ok(result)
+    }
+
+    /// Parse a ternary string aligned with a list of variables.
+    ///
+    /// The string must provide a character for each variable: ``0`` or ``1`` for fixed
+    /// variables and ``-`` for free ones.
+    pub fn parse_ternary(descr: &str, variables: &[usize]) -> Result<Pattern, ParseError> {
+        let len = descr.chars().count();
+        if len != variables.len() {
+            return Err(parse_error(format!(
+                "Pattern '{}' has {} characters for {} variables",
+                descr,
+                len,
+                variables.len()
+            )));
+        }
+
+        let mut p = Pattern::new();
+        for (c, uid) in descr.chars().zip(variables.iter()) {
+            match c {
+                '0' => p.set(*uid, false),
+                '1' => p.set(*uid, true),
+                '-' => (),
+                _ => {
+                    let msg = format!("Invalid character '{}' in pattern '{}'", c, descr);
+                    return Err(parse_error(msg));
+                }
+            }
+        }
+        Ok(p)
+    }
+
+    /// Parse a comma-separated list of named restrictions.
+    ///
+    /// Each restriction can fix the level of a component (``A=2``), the value of a specific
+    /// threshold (``A:2=0``), or use a shorthand for active (``A``) and inactive (``!A``)
+    /// Boolean variables. An empty string denotes the unrestricted pattern.
+    pub fn parse_named(descr: &str, grp: &impl GroupedVariables) -> Result<Pattern, ParseError> {
+        let mut p = Pattern::new();
+        if descr.trim().is_empty() {
+            return Ok(p);
+        }
+
+        for item in descr.split(',').map(str::trim) {
+            let (name, value) = match item.strip_prefix('!').or_else(|| item.strip_prefix('~')) {
+                Some(name) => (name.trim(), 0),
+                None => match item.split_once('=') {
+                    None => (item, 1),
+                    Some((name, value)) => (name.trim(), value.trim().parse()?),
+                },
+            };
+            if name.is_empty() {
+                return Err(parse_error(format!("Missing name in pattern '{}'", descr)));
+            }
+
+            let (cpt, threshold) = match name.split_once(':') {
+                None => (name, None),
+                Some((cpt, t)) => (cpt, Some(t.parse()?)),
+            };
+            let cid = grp
+                .get_handle(cpt)
+                .ok_or_else(|| parse_error(format!("Unknown component '{}'", cpt)))?;
+
+            match threshold {
+                Some(t) => {
+                    if value > 1 {
+                        return Err(ParseError::InvalidValue(name.to_owned(), value));
+                    }
+                    let vid = grp
+                        .get_variable(cid, t)
+                        .ok_or(ParseError::InvalidValue(cpt.to_owned(), t))?;
+                    p.set(vid, value == 1);
+                }
+                None => {
+                    let variables = grp.get_variables(cid);
+                    if value > variables.len() {
+                        return Err(ParseError::InvalidValue(name.to_owned(), value));
+                    }
+                    for (i, vid) in variables.iter().enumerate() {
+                        p.set(*vid, i < value);
+                    }
+                }
+            }
+        }
+        Ok(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::pattern::Pattern;
+    use crate::helper::error::ParseError;
+    use crate::variables::{GroupedVariables, ModelVariables};
+
+    #[test]
+    fn parse_patterns() {
+        let mut grp = ModelVariables::default();
+        let a = grp.ensure("A");
+        let b = grp.ensure("B");
+        let c = grp.ensure("C");
+        let b2 = grp.ensure_threshold(b, 2).unwrap();
+
+        let mut expected = Pattern::new();
+        expected.set(a, true);
+        expected.set(c, false);
+        assert_eq!(Pattern::parse("1--0", &grp).unwrap(), expected);
+        assert_eq!(Pattern::parse("A=1, C=0", &grp).unwrap(), expected);
+        assert_eq!(Pattern::parse("A,!C", &grp).unwrap(), expected);
+
+        // Multivalued components
+        let p = Pattern::parse("B=1", &grp).unwrap();
+        assert!(p.is_fixed_at(b, true) && p.is_fixed_at(b2, false));
+        let p = Pattern::parse("B:2=0", &grp).unwrap();
+        assert!(p.is_fixed_at(b2, false) && !p.is_fixed(b));
+        assert_eq!(Pattern::parse("", &grp).unwrap(), Pattern::new());
+        let all = Pattern::parse_all(&["A".to_owned(), "-0-0".to_owned()], &grp).unwrap();
+        assert!(all.is_fixed_at(a, true) && all.is_fixed_at(b, false));
+        assert!(Pattern::parse_all(&["A".to_owned(), "0---".to_owned()], &grp).is_err());
+
+        // Errors
+        assert!(Pattern::parse("1-", &grp).is_err());
+        assert!(Pattern::parse_ternary("1-x", &[a, b, c]).is_err());
+        assert!(Pattern::parse("A,D", &grp).is_err());
+        assert!(Pattern::parse("A=x", &grp).is_err());
+        assert!(matches!(
+            Pattern::parse("B=3", &grp),
+            Err(ParseError::InvalidValue(_, 3))
+        ));
+        assert!(matches!(
+            Pattern::parse("C:2=1", &grp),
+            Err(ParseError::InvalidValue(_, 2))
+        ));
+        assert!(matches!(
+            Pattern::parse("A:100000000=1", &grp),
+            Err(ParseError::InvalidValue(_, 100000000))
+        ));
+    }
+}
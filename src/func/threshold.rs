@@ -0,0 +1,325 @@
+//! Linear threshold functions defined by weighted regulators
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::func;
+use crate::func::expr::Expr;
+use crate::func::gen::Sign;
+use crate::func::monotonicity::Effect;
+use crate::func::state::State;
+use crate::func::{BoolRepr, Formula, Repr, TrivialNamer};
+
+/// Maximal number of regulators considered when searching threshold weights
+static MAX_REGULATORS: usize = 12;
+
+/// Maximal number of passes over the truth table when searching threshold weights
+static MAX_EPOCHS: usize = 1000;
+
+/// A Boolean function comparing the weighted sum of its active regulators to a threshold.
+///
+/// The function is true if the sum is above the threshold and false if it is below.
+/// When the sum equals the threshold, the function keeps the current value of the persistent
+/// variable if it is defined, otherwise it is false (self-degradation).
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct ThresholdFunction {
+    weights: BTreeMap<usize, isize>,
+    threshold: isize,
+    persistent: Option<usize>,
+}
+
+impl ThresholdFunction {
+    /// Create a new function without any regulator, with self-degradation
+    pub fn new(threshold: isize) -> Self {
+        ThresholdFunction {
+            weights: BTreeMap::new(),
+            threshold,
+            persistent: None,
+        }
+    }
+
+    /// Create the classical threshold function where each regulator has a unit weight
+    /// given by its sign and the threshold is zero.
+    pub fn from_signs<'a>(regulators: impl Iterator<Item = (&'a usize, &'a Sign)>) -> Self {
+        let mut f = ThresholdFunction::new(0);
+        for (uid, sign) in regulators {
+            match sign {
+                Sign::POSITIVE => f.set_weight(*uid, 1),
+                Sign::NEGATIVE => f.set_weight(*uid, -1),
+            }
+        }
+        f
+    }
+
+    /// Set the weight of a regulator, a null weight removes it
+    pub fn set_weight(&mut self, uid: usize, weight: isize) {
+        if weight == 0 {
+            self.weights.remove(&uid);
+        } else {
+            self.weights.insert(uid, weight);
+        }
+    }
+
+    pub fn weight(&self, uid: usize) -> isize {
+        *self.weights.get(&uid).unwrap_or(&0)
+    }
+
+    pub fn threshold(&self) -> isize {
+        self.threshold
+    }
+
+    /// Keep the value of a variable (usually the target) when the sum equals the threshold.
+    /// Without it, the function degrades to false in this case.
+    pub fn set_persistent(&mut self, uid: Option<usize>) {
+        self.persistent = uid;
+    }
+
+    pub fn persistent(&self) -> Option<usize> {
+        self.persistent
+    }
+
+    /// Generate the corresponding expression.
+    ///
+    /// Regulators are decomposed by decreasing absolute weight, and branches for which the
+    /// remaining regulators can not change the result are cut.
+    pub fn to_expr(&self) -> Expr {
+        let mut regulators: Vec<(usize, isize)> =
+            self.weights.iter().map(|(u, w)| (*u, *w)).collect();
+        regulators.sort_by_key(|(_, w)| -w.abs());
+        let expr = self.decompose(&regulators, 0);
+        expr.simplify().unwrap_or(expr)
+    }
+
+    fn decompose(&self, regulators: &[(usize, isize)], partial: isize) -> Expr {
+        let min: isize = regulators.iter().map(|(_, w)| *w).filter(|w| *w < 0).sum();
+        let max: isize = regulators.iter().map(|(_, w)| *w).filter(|w| *w > 0).sum();
+        if partial + min > self.threshold {
+            return Expr::TRUE;
+        }
+        if partial + max < self.threshold {
+            return Expr::FALSE;
+        }
+        if regulators.is_empty() {
+            // The sum equals the threshold
+            return match self.persistent {
+                None => Expr::FALSE,
+                Some(u) => Expr::ATOM(u),
+            };
+        }
+
+        let (uid, w) = regulators[0];
+        let high = self.decompose(&regulators[1..], partial + w);
+        let low = self.decompose(&regulators[1..], partial);
+        if high == low {
+            return high;
+        }
+
+        // The function is monotone in each regulator: the branch with the highest
+        // sum includes the other one
+        if w > 0 {
+            or_const(low, and_const(Expr::ATOM(uid), high))
+        } else {
+            or_const(high, and_const(Expr::NATOM(uid), low))
+        }
+    }
+
+    /// Search a threshold function equivalent to a given expression.
+    ///
+    /// Threshold functions are monotone in each regulator: functions with dual
+    /// regulators are rejected directly. Otherwise, integer weights are searched by iterative
+    /// corrections on the truth table (perceptron rule), which converges for all threshold
+    /// functions. The search is restricted to functions with a limited number of regulators
+    /// and may give up for functions requiring very large weights.
+    pub fn from_expr(expr: &Expr) -> Option<ThresholdFunction> {
+        let effects = Formula::from(expr.clone()).regulator_effects();
+        if effects.values().any(|e| *e == Effect::DUAL) {
+            return None;
+        }
+        let regulators: Vec<usize> = effects
+            .iter()
+            .filter(|(_, e)| e.is_functional())
+            .map(|(u, _)| *u)
+            .collect();
+        if regulators.len() > MAX_REGULATORS {
+            return None;
+        }
+
+        let table: Vec<(State, bool)> = (0..1usize << regulators.len())
+            .map(|mask| {
+                let state: State = regulators
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| mask & (1 << i) != 0)
+                    .map(|(_, u)| *u)
+                    .collect();
+                let value = expr.eval(&state);
+                (state, value)
+            })
+            .collect();
+
+        // Search weights such that the function is true iff the sum is at least theta
+        let mut weights = vec![0isize; regulators.len()];
+        let mut theta = 0isize;
+        for _ in 0..MAX_EPOCHS {
+            let mut errors = false;
+            for (state, value) in table.iter() {
+                let sum: isize = regulators
+                    .iter()
+                    .zip(weights.iter())
+                    .filter(|(u, _)| state.contains(**u))
+                    .map(|(_, w)| *w)
+                    .sum();
+                let delta = match (value, sum >= theta) {
+                    (true, false) => 1,
+                    (false, true) => -1,
+                    _ => continue,
+                };
+                errors = true;
+                theta -= delta;
+                for (u, w) in regulators.iter().zip(weights.iter_mut()) {
+                    if state.contains(*u) {
+                        *w += delta;
+                    }
+                }
+            }
+
+            if !errors {
+                let mut result = ThresholdFunction::new(theta - 1);
+                for (u, w) in regulators.iter().zip(weights.iter()) {
+                    result.set_weight(*u, *w);
+                }
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+fn and_const(lit: Expr, e: Expr) -> Expr {
+    match e {
+        Expr::TRUE => lit,
+        Expr::FALSE => Expr::FALSE,
+        _ => lit.and(&e),
+    }
+}
+
+fn or_const(a: Expr, b: Expr) -> Expr {
+    match (a, b) {
+        (Expr::TRUE, _) | (_, Expr::TRUE) => Expr::TRUE,
+        (Expr::FALSE, e) | (e, Expr::FALSE) => e,
+        (a, b) => a.or(&b),
+    }
+}
+
+impl BoolRepr for ThresholdFunction {
+    fn into_repr(self) -> Repr {
+        Repr::THRESHOLD(Rc::new(self))
+    }
+
+    fn eval(&self, state: &State) -> bool {
+        let sum: isize = self
+            .weights
+            .iter()
+            .filter(|(u, _)| state.contains(**u))
+            .map(|(_, w)| *w)
+            .sum();
+        match sum.cmp(&self.threshold) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => self.persistent.map(|u| state.contains(u)).unwrap_or(false),
+        }
+    }
+}
+
+impl fmt::Display for ThresholdFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        func::Grouped::gfmt(self, &TrivialNamer {}, f)
+    }
+}
+
+impl func::Grouped for ThresholdFunction {
+    fn gfmt(&self, namer: &dyn func::VariableNamer, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.weights.is_empty() {
+            write!(f, "0")?;
+        }
+        for (i, (uid, w)) in self.weights.iter().enumerate() {
+            if *w < 0 {
+                write!(f, "{}", if i > 0 { " - " } else { "-" })?;
+            } else if i > 0 {
+                write!(f, " + ")?;
+            }
+            if w.abs() != 1 {
+                write!(f, "{}*", w.abs())?;
+            }
+            namer.format_name(f, *uid)?;
+        }
+        write!(f, " > {}", self.threshold)?;
+        if let Some(u) = self.persistent {
+            write!(f, ", keep ")?;
+            namer.format_name(f, u)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::func::state::State;
+    use crate::func::threshold::ThresholdFunction;
+    use crate::func::BoolRepr;
+
+    fn equivalent(a: &impl BoolRepr, b: &impl BoolRepr, variables: &[usize]) -> bool {
+        (0..1usize << variables.len()).all(|mask| {
+            let state: State = variables
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, u)| *u)
+                .collect();
+            a.eval(&state) == b.eval(&state)
+        })
+    }
+
+    #[test]
+    fn threshold_to_expr() {
+        // 2*v1 + v2 + v3 - 2*v4 > 1, keep v0
+        let mut f = ThresholdFunction::new(1);
+        f.set_weight(1, 2);
+        f.set_weight(2, 1);
+        f.set_weight(3, 1);
+        f.set_weight(4, -2);
+        f.set_persistent(Some(0));
+
+        let expr = f.to_expr();
+        assert!(equivalent(&f, &expr, &[0, 1, 2, 3, 4]));
+        assert_eq!(format!("{}", f), "2*v1 + v2 + v3 - 2*v4 > 1, keep v0");
+
+        // Without regulators
+        let f = ThresholdFunction::new(-1);
+        assert_eq!(f.to_expr(), Expr::TRUE);
+    }
+
+    #[test]
+    fn threshold_from_expr() {
+        let a = Expr::ATOM(1);
+        let b = Expr::ATOM(2);
+        let c = Expr::ATOM(3);
+
+        // Majority and negative regulators
+        let expr = a.and(&b).or(&a.and(&c)).or(&b.and(&c)).and(&Expr::NATOM(4));
+        let f = ThresholdFunction::from_expr(&expr).unwrap();
+        assert!(equivalent(&f, &expr, &[1, 2, 3, 4]));
+        assert!(f.weight(4) < 0);
+
+        // Xor is not a threshold function
+        let expr = a.and(&b.not()).or(&a.not().and(&b));
+        assert_eq!(ThresholdFunction::from_expr(&expr), None);
+
+        // Neither is this unate function
+        let expr = a.and(&b).or(&c.and(&Expr::ATOM(4)));
+        assert_eq!(ThresholdFunction::from_expr(&expr), None);
+    }
+}
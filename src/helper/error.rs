@@ -26,6 +26,9 @@ pub enum LomakError {
     #[error("No model was provided")]
     MissingModel(),
 
+    #[error("No model named \"{0}\"")]
+    UnknownModel(String),
+
     #[error(transparent)]
     Generic(#[from] GenericError),
 }
@@ -46,6 +49,9 @@ pub enum ParseError {
 
     #[error("Error parsing: {0}")]
     ParseMessage(#[from] GenericError),
+
+    #[error("Invalid value for {0}: {1}")]
+    InvalidValue(String, usize),
 }
 
 #[derive(Error, Debug)]
@@ -88,9 +94,14 @@ pub type CanFail<E> = Result<(), E>;
 
 pub type EmptyLomakResult = LomakResult<()>;
 
+impl<R: pest::RuleType + 'static> From<pest::error::Error<R>> for ParseError {
+    fn from(e: pest::error::Error<R>) -> Self {
+        ParseTxtError::new(Box::new(e)).into()
+    }
+}
+
 impl<R: pest::RuleType + 'static> From<pest::error::Error<R>> for LomakError {
     fn from(e: pest::error::Error<R>) -> Self {
-        let e = ParseTxtError::new(Box::new(e));
         let e: ParseError = e.into();
         e.into()
     }
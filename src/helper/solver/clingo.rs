@@ -33,7 +33,7 @@ impl Solver for ClingoProblem {
             .expect("Failed creating Control.");
     }
 
-    fn solve<'a>(&'a mut self) -> Box<dyn SolverResults + 'a> {
+    fn solve<'a>(&'a mut self) -> Box<dyn SolverResults<'a> + 'a> {
         Box::new(self.solve_clingo())
     }
 }
@@ -65,7 +65,7 @@ impl ClingoProblem {
         }
     }
 
-    pub fn solve_clingo(&mut self) -> ClingoResults {
+    pub fn solve_clingo(&mut self) -> ClingoResults<'_> {
         // ground the base part
         let parts = vec![Part::new("base", &[]).unwrap()];
         self.ctl
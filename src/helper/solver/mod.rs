@@ -27,7 +27,7 @@ pub trait Solver {
 
     fn add(&mut self, instruct: &str);
 
-    fn solve<'a>(&'a mut self) -> Box<dyn SolverResults + 'a>;
+    fn solve<'a>(&'a mut self) -> Box<dyn SolverResults<'a> + 'a>;
 }
 
 pub trait SolverResults<'a>: Iterator<Item = SolverSolution> {
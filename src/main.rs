@@ -33,7 +33,7 @@ fn main() {
             Ok(_) => (),
             Err(e) => {
                 eprintln!("{}", e);
-                break;
+                std::process::exit(1);
             }
         }
     }
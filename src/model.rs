@@ -6,23 +6,31 @@
 //! Logical rules define possible changes of activity over time, depending on the current model state.
 
 use std::cell::{Ref, RefCell, RefMut};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt;
 use std::ops::Deref;
 use std::rc::Rc;
 use std::slice::Iter;
 
 use crate::func::expr::*;
+use crate::func::pattern::Pattern;
 use crate::func::*;
-use crate::helper::error::EmptyLomakResult;
+use crate::helper::error::{CanFail, EmptyLomakResult, GenericError, ParseError};
+use crate::model::history::History;
 use crate::model::layout::{Layout, NodeLayoutInfo};
+use crate::model::modifier::booleanize::Booleanization;
+use crate::model::modifier::reduction::Reduction;
+use crate::model::provenance::Provenance;
 use crate::model::rule::Rules;
-use crate::variables::{GroupedVariables, ModelVariables, Variable, MAXVAL};
+use crate::variables::{GroupedVariables, ModelVariables, NamePolicy, Variable};
 
 pub mod actions;
+pub mod graph;
+pub mod history;
 pub mod io;
 pub mod layout;
 pub mod modifier;
+pub mod provenance;
 pub mod rule;
 
 /// A model contains a list of named components and an associated Boolean variable for each qualitative threshold.
@@ -34,6 +42,10 @@ pub struct QModel {
     variables: Rc<ModelVariables>,
     rules: Rc<Rules>,
     layout: Option<Rc<Layout>>,
+    reduction: Option<Rc<Reduction>>,
+    booleanization: Option<Rc<Booleanization>>,
+    provenance: Provenance,
+    history: History,
 }
 
 /// Sharable model reference
@@ -60,27 +72,68 @@ impl GroupedVariables for QModel {
         self.variables.get_variables(cid)
     }
 
-    fn components(&self) -> Iter<usize> {
+    fn components(&self) -> Iter<'_, usize> {
         self.variables.components()
     }
 
-    fn variables(&self) -> Iter<usize> {
+    fn variables(&self) -> Iter<'_, usize> {
         self.variables.variables()
     }
 
-    fn ensure(&mut self, name: &str) -> usize {
-        let handle = Rc::make_mut(&mut self.variables).ensure(name);
-        Rc::make_mut(&mut self.rules).ensure(self.variables.component(handle).unwrap());
-        handle
+    fn try_ensure(&mut self, name: &str) -> Result<usize, GenericError> {
+        if let Some(handle) = self.variables.find_identifier(name) {
+            return Ok(handle);
+        }
+        self.try_edit(&format!("add {}", name), |model| {
+            let handle = Rc::make_mut(&mut model.variables).try_ensure(name)?;
+            Rc::make_mut(&mut model.rules).ensure(model.variables.component(handle).unwrap());
+            Ok(handle)
+        })
     }
 
     /// Find or create a variable for an existing component and a specific threshold value
-    fn ensure_threshold(&mut self, cid: usize, value: usize) -> usize {
-        Rc::make_mut(&mut self.variables).ensure_threshold(cid, value)
+    fn ensure_threshold(&mut self, cid: usize, value: usize) -> Result<usize, GenericError> {
+        if let Some(vid) = self.get_variable(cid, value) {
+            return Ok(vid);
+        }
+        let description = format!("add threshold {}:{}", self.get_name(cid), value);
+        self.edit(&description, |model| {
+            Rc::make_mut(&mut model.variables).ensure_threshold(cid, value)
+        })
     }
 
     fn set_name(&mut self, uid: usize, name: &str) -> Result<bool, &'static str> {
-        Rc::make_mut(&mut self.variables).set_name(uid, name)
+        let old_name = self.get_name(uid).to_owned();
+        let description = format!("rename {} to {}", old_name, name);
+        self.edit(&description, |model| {
+            let result = Rc::make_mut(&mut model.variables).set_name(uid, name);
+            if result == Ok(true) && old_name != name {
+                model.add_modification("rename", vec![old_name, name.to_owned()]);
+            }
+            result
+        })
+    }
+}
+
+impl QModel {
+    /// Rename several components at once, see ModelVariables::set_names
+    pub fn set_names(&mut self, names: &[(usize, String)]) -> Result<usize, GenericError> {
+        self.variables.check_names(names)?;
+        let mut arguments = vec![];
+        for (cid, name) in names {
+            if self.get_name(*cid) != name {
+                arguments.push(self.get_name(*cid).to_owned());
+                arguments.push(name.to_owned());
+            }
+        }
+        let description = format!("rename {} components", arguments.len() / 2);
+        self.edit(&description, |model| {
+            let count = Rc::make_mut(&mut model.variables).set_names(names)?;
+            if count > 0 {
+                model.add_modification("rename", arguments);
+            }
+            Ok(count)
+        })
     }
 }
 
@@ -90,6 +143,30 @@ impl QModel {
         self.variables.clone()
     }
 
+    /// Select the normalisation of invalid names for new components
+    pub fn set_name_policy(&mut self, policy: NamePolicy) {
+        Rc::make_mut(&mut self.variables).set_name_policy(policy);
+    }
+
+    /// The original name of a component, if it differs from its identifier
+    pub fn get_label(&self, cid: usize) -> Option<&str> {
+        self.variables.get_label(cid)
+    }
+
+    /// Map identifiers to original names, writers can use it to restore the original names
+    pub fn labels(&self) -> impl Iterator<Item = (usize, &str)> + '_ {
+        self.variables.labels()
+    }
+
+    pub fn set_label(&mut self, cid: usize, label: &str) -> bool {
+        Rc::make_mut(&mut self.variables).set_label(cid, label)
+    }
+
+    /// Find a variable by identifier, ignoring the labels
+    pub fn find_identifier(&self, name: &str) -> Option<usize> {
+        self.variables.find_identifier(name)
+    }
+
     pub fn frozen_rules(&self) -> Rc<HashMap<usize, Formula>> {
         let mut m = HashMap::new();
         for u in self.variables() {
@@ -101,7 +178,35 @@ impl QModel {
 
     /// Assign a Boolean condition for a specific threshold
     pub fn push_cpt_rule(&mut self, cid: usize, value: usize, rule: Formula) {
-        Rc::make_mut(&mut self.rules).push(cid, value, rule);
+        let description = format!("add rule for {}:{}", self.get_name(cid), value);
+        self.edit(&description, |model| {
+            Rc::make_mut(&mut model.rules).push(cid, value, rule)
+        });
+    }
+
+    /// Parse a Boolean condition and use it for a specific threshold of a component.
+    ///
+    /// This replaces any existing condition for the same target value.
+    /// Components and thresholds used in the condition are created if needed.
+    pub fn set_rule_from_str(
+        &mut self,
+        cid: usize,
+        value: usize,
+        text: &str,
+    ) -> CanFail<ParseError> {
+        let cid = match self.variables.component(cid) {
+            None => return Err(GenericError::new(format!("Unknown component {}", cid)).into()),
+            Some(c) => c,
+        };
+        let description = format!("set rule for {}:{}", self.get_name(cid), value);
+        self.try_edit(&description, |model| {
+            let expr = Expr::parse(text, model)?;
+            if value > 0 {
+                model.ensure_threshold(cid, value)?;
+            }
+            Rc::make_mut(&mut model.rules).set(cid, value, Formula::from(expr));
+            Ok(())
+        })
     }
 
     pub fn get_layout(&self) -> Option<Rc<Layout>> {
@@ -152,35 +257,300 @@ impl QModel {
 }
 
 impl QModel {
-    /// Enforce the activity of a specific variable
+    /// Enforce the activity of a specific variable.
+    ///
+    /// An active variable restricts its component to the corresponding threshold or above,
+    /// an inactive variable restricts it below this threshold.
     pub fn lock_variable(&mut self, vid: usize, value: bool) {
-        let var = &self.variables.variable(vid);
-        if var.is_none() {
-            return;
-        }
-        let var = var.unwrap();
+        let var = match self.variables.variable(vid) {
+            None => return,
+            Some(v) => *v,
+        };
         let cpt = var.component;
-        let val = var.value;
-        if value {
-            self.restrict_component(cpt, val, MAXVAL);
+        let max = self.get_variables(cpt).len();
+        let (min, max) = if value {
+            (var.value, max)
         } else {
-            self.restrict_component(cpt, 0, val - 1);
+            (0, var.value.saturating_sub(1))
+        };
+        self.restrict_component(cpt, min, max)
+            .expect("The levels of an existing threshold are valid");
+    }
+
+    /// Enforce the activity of a regulator variable in the rules of a single target component.
+    ///
+    /// Similarly to locked variables, a locked active regulator is at least at the
+    /// corresponding threshold, while a locked inactive regulator is below it.
+    /// Other targets of the regulator are not affected.
+    pub fn lock_regulator(&mut self, vid: usize, target: usize, value: bool) {
+        let var = match self.variables.variable(vid) {
+            None => return,
+            Some(v) => *v,
+        };
+        let mut locked = Pattern::new();
+        for (idx, v) in self.get_variables(var.component).iter().enumerate() {
+            let threshold = idx + 1;
+            if value && threshold <= var.value {
+                locked.set(*v, true);
+            } else if !value && threshold >= var.value {
+                locked.set(*v, false);
+            }
+        }
+
+        let mut rule = match self.rules.get(target) {
+            None => return,
+            Some(r) => r.clone(),
+        };
+        rule.map_assignments(|assign| {
+            let expr: Rc<Expr> = assign.formula.convert_as();
+            if let Some(e) = expr.replace_variables(&mut locked) {
+                assign.formula.set(e.simplify().unwrap_or(e));
+            }
+        });
+        let description = format!(
+            "lock {}:{} in {}",
+            self.get_name(var.component),
+            var.value,
+            self.get_name(target)
+        );
+        self.edit(&description, |model| {
+            Rc::make_mut(&mut model.rules).replace(target, rule)
+        });
+    }
+
+    /// Restrict the activity of a component, fails if the range is not valid for this component
+    pub fn restrict_component(
+        &mut self,
+        cid: usize,
+        min: usize,
+        max: usize,
+    ) -> CanFail<GenericError> {
+        if min > max || max > self.get_variables(cid).len() {
+            return Err(GenericError::new(format!(
+                "Invalid range for {}: {}..{}",
+                self.get_name(cid),
+                min,
+                max
+            )));
+        }
+        let description = format!("restrict {} to {}..{}", self.get_name(cid), min, max);
+        self.edit(&description, |model| {
+            Rc::make_mut(&mut model.rules).restrict_component(cid, min, max)
+        });
+        Ok(())
+    }
+
+    /// Enforce the level of a component, fails if the level is above its maximal level
+    pub fn lock_component(&mut self, cid: usize, value: usize) -> CanFail<GenericError> {
+        if value > self.get_variables(cid).len() {
+            return Err(GenericError::new(format!(
+                "Invalid level for {}: {}",
+                self.get_name(cid),
+                value
+            )));
+        }
+        let description = format!("lock {} at {}", self.get_name(cid), value);
+        self.edit(&description, |model| {
+            Rc::make_mut(&mut model.rules).lock_component(cid, value)
+        });
+        Ok(())
+    }
+
+    /// Remove a component with its rules and layout.
+    ///
+    /// The rules of other components are not updated: they should not depend on this component.
+    /// Returns false if the component does not exist.
+    pub fn remove_component(&mut self, cid: usize) -> bool {
+        if !self.components().any(|c| *c == cid) {
+            return false;
         }
+        let name = self.get_name(cid).to_owned();
+        self.edit(&format!("remove {}", name), |model| {
+            model.discard_component(cid);
+            model.add_modification("remove", vec![name]);
+        });
+        true
     }
 
-    pub fn lock_regulator(&mut self, _vid: usize, _target: usize, _value: bool) {
-        // FIXME: implement locking of interactions
-        unimplemented!()
+    /// Remove a component as part of a modifier, which records its own modification
+    pub(crate) fn discard_component(&mut self, cid: usize) {
+        self.edit(&format!("remove {}", self.get_name(cid)), |model| {
+            Rc::make_mut(&mut model.variables).remove_component(cid);
+            Rc::make_mut(&mut model.rules).remove(cid);
+            if let Some(layout) = model.layout.as_mut() {
+                Rc::make_mut(layout).remove(cid);
+            }
+        });
     }
 
-    /// Restrict the activity of a component
-    pub fn restrict_component(&mut self, cid: usize, min: usize, max: usize) {
-        Rc::make_mut(&mut self.rules).restrict_component(cid, min, max);
+    /// Remove a threshold of a component, merging the two adjacent levels.
+    ///
+    /// The rules of the component are shifted accordingly. The first threshold can not be
+    /// removed, and the removed variable should not be used in the rules of any component.
+    pub fn remove_threshold(&mut self, cid: usize, value: usize) -> CanFail<GenericError> {
+        let vid = self
+            .get_variable(cid, value)
+            .filter(|_| value > 1)
+            .ok_or_else(|| {
+                GenericError::new(format!(
+                    "Can not remove threshold {} of {}",
+                    value,
+                    self.get_name(cid)
+                ))
+            })?;
+        for target in self.components() {
+            let used = self.rules.get(*target).is_some_and(|rules| {
+                rules.assignments().any(|a| {
+                    let e: Rc<Expr> = a.convert();
+                    e.contains_literal(vid, false) || e.contains_literal(vid, true)
+                })
+            });
+            if used {
+                return Err(GenericError::new(format!(
+                    "{} is used in the rules of {}",
+                    self.get_name(vid),
+                    self.get_name(*target)
+                )));
+            }
+        }
+
+        let name = self.get_name(vid).to_owned();
+        self.edit(&format!("remove threshold {}", name), |model| {
+            Rc::make_mut(&mut model.variables).remove_threshold(cid, value);
+            Rc::make_mut(&mut model.rules).remove_threshold(cid, value);
+            model.add_modification("remove_threshold", vec![name]);
+        });
+        Ok(())
     }
 
-    /// Enforce the activity of a specific variable
-    pub fn lock_component(&mut self, cid: usize, value: usize) {
-        Rc::make_mut(&mut self.rules).lock_component(cid, value);
+    /// Change the order of the components, which must contain each component exactly once
+    pub fn reorder_components(&mut self, order: &[usize]) -> CanFail<GenericError> {
+        let components: HashSet<&usize> = self.components().collect();
+        if order.len() != components.len() || order.iter().collect::<HashSet<_>>() != components {
+            return Err(GenericError::new(
+                "The new order must list each component exactly once".to_owned(),
+            ));
+        }
+        let names = order.iter().map(|c| self.get_name(*c).to_owned()).collect();
+        self.set_order(order, "reorder", names);
+        Ok(())
+    }
+
+    /// Apply a permutation of the existing components and record it in the lineage
+    fn set_order(&mut self, order: &[usize], modifier: &str, arguments: Vec<String>) {
+        if self.components().eq(order.iter()) {
+            return;
+        }
+        self.edit(&format!("{} components", modifier), |model| {
+            Rc::make_mut(&mut model.variables).reorder_components(order);
+            model.add_modification(modifier, arguments);
+        });
+    }
+
+    /// Sort the components by name
+    pub fn sort_components_by_name(&mut self) {
+        let mut order: Vec<usize> = self.components().copied().collect();
+        order.sort_by(|a, b| self.get_name(*a).cmp(self.get_name(*b)));
+        self.set_order(&order, "sort", vec!["name".to_owned()]);
+    }
+
+    /// Sort the components following the regulatory graph.
+    ///
+    /// Components are placed after their regulators when possible. In a cycle, the component
+    /// with the fewest unplaced regulators comes first, ties preserve the current order.
+    pub fn sort_components_by_graph(&mut self) {
+        let components: Vec<usize> = self.components().copied().collect();
+        let position: HashMap<usize, usize> = components
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (*c, i))
+            .collect();
+        let mut targets: Vec<HashSet<usize>> = vec![HashSet::new(); components.len()];
+        let mut unplaced = vec![0; components.len()];
+        for i in self.interaction_graph() {
+            if let (Some(&src), Some(&tgt)) = (position.get(&i.source), position.get(&i.target)) {
+                if src != tgt && targets[src].insert(tgt) {
+                    unplaced[tgt] += 1;
+                }
+            }
+        }
+
+        // Queue of the remaining components by number of unplaced regulators and position
+        let mut queue: BTreeSet<(usize, usize)> =
+            unplaced.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        let mut order = Vec::with_capacity(components.len());
+        while let Some((_, idx)) = queue.pop_first() {
+            order.push(components[idx]);
+            for &tgt in &targets[idx] {
+                if queue.remove(&(unplaced[tgt], tgt)) {
+                    unplaced[tgt] -= 1;
+                    queue.insert((unplaced[tgt], tgt));
+                }
+            }
+        }
+        self.set_order(&order, "sort", vec!["graph".to_owned()]);
+    }
+
+    /// The threshold variables of the multivalued model if this model was booleanized.
+    ///
+    /// The mapping is dropped once the components or rules of the Boolean model are modified.
+    pub fn booleanization(&self) -> Option<Rc<Booleanization>> {
+        self.booleanization
+            .as_ref()
+            .filter(|b| b.is_current(self))
+            .cloned()
+    }
+
+    /// Replace the content of this model with another model as a single reversible edit.
+    ///
+    /// The history is kept, the other model provides the provenance: it should be derived from
+    /// this model.
+    pub fn replace_with(&mut self, description: &str, other: QModel) {
+        self.edit(description, |model| {
+            model.variables = other.variables;
+            model.rules = other.rules;
+            model.layout = other.layout;
+            model.reduction = other.reduction;
+            model.booleanization = other.booleanization;
+            model.provenance = other.provenance;
+        });
+    }
+
+    /// The eliminated components if this model was reduced
+    pub fn reduction(&self) -> Option<Rc<Reduction>> {
+        self.reduction.clone()
+    }
+
+    pub fn provenance(&self) -> &Provenance {
+        &self.provenance
+    }
+
+    /// Create an empty model with a given provenance
+    pub fn with_provenance(provenance: Provenance) -> Self {
+        QModel {
+            variables: Rc::default(),
+            rules: Rc::default(),
+            layout: None,
+            reduction: None,
+            booleanization: None,
+            provenance,
+            history: History::default(),
+        }
+    }
+
+    pub fn set_provenance(&mut self, provenance: Provenance) {
+        self.provenance = provenance;
+    }
+
+    /// Record a modifier and its arguments in the lineage of this model
+    pub fn add_modification(&mut self, modifier: &str, arguments: Vec<String>) {
+        self.provenance.push(modifier, arguments);
+    }
+
+    pub fn set_reduction(&mut self, reduction: Reduction) {
+        self.edit("set reduction", |model| {
+            model.reduction = Some(Rc::new(reduction))
+        });
     }
 }
 
@@ -193,7 +563,10 @@ impl QModel {
     }
 
     pub fn set_bounding_box(&mut self, uid: usize, bb: NodeLayoutInfo) {
-        self.layout_mut().set_bounding_box(uid, bb);
+        let description = format!("move {}", self.get_name(uid));
+        self.edit(&description, |model| {
+            model.layout_mut().set_bounding_box(uid, bb)
+        });
     }
 
     pub fn get_bounding_box(&self, uid: usize) -> Option<&NodeLayoutInfo> {
@@ -204,6 +577,21 @@ impl QModel {
     }
 }
 
+/// A clone shares the origin of the original model and starts with an empty history
+impl Clone for QModel {
+    fn clone(&self) -> Self {
+        QModel {
+            variables: Rc::clone(&self.variables),
+            rules: Rc::clone(&self.rules),
+            layout: self.layout.clone(),
+            reduction: self.reduction.clone(),
+            booleanization: self.booleanization.clone(),
+            provenance: self.provenance.derive(),
+            history: History::default(),
+        }
+    }
+}
+
 impl SharedModel {
     pub fn with(model: QModel) -> Self {
         Self {
@@ -211,14 +599,29 @@ impl SharedModel {
         }
     }
 
-    pub fn borrow(&self) -> Ref<QModel> {
+    pub fn borrow(&self) -> Ref<'_, QModel> {
         self.rc.as_ref().borrow()
     }
 
-    pub fn borrow_mut(&self) -> RefMut<QModel> {
+    pub fn borrow_mut(&self) -> RefMut<'_, QModel> {
         self.rc.as_ref().borrow_mut()
     }
 
+    /// Revert the last edit of the model, returns its description
+    pub fn undo(&self) -> Option<String> {
+        self.borrow_mut().undo()
+    }
+
+    /// Apply again the last undone edit of the model, returns its description
+    pub fn redo(&self) -> Option<String> {
+        self.borrow_mut().redo()
+    }
+
+    /// Enable or disable the recording of edits, returns the previous setting
+    pub fn set_recording(&self, b: bool) -> bool {
+        self.borrow_mut().set_recording(b)
+    }
+
     pub fn save(&self, filename: &str, fmt: Option<&str>) -> EmptyLomakResult {
         let model = self.borrow();
         io::save_model(model.deref(), filename, fmt)
@@ -277,8 +680,12 @@ impl fmt::Debug for QModel {
 mod tests {
 
     use super::*;
-    use crate::model::actions::fixpoints::*;
+
     use crate::model::actions::trapspaces::*;
+    use crate::model::io;
+    use crate::model::modifier::merge::Merger;
+    use crate::model::modifier::multivalued::ThresholdGroups;
+    use crate::variables::{is_valid_name, MAX_LEVEL};
 
     #[test]
     fn the_regex() {
@@ -286,13 +693,13 @@ mod tests {
         {
             let mut model = smodel.borrow_mut();
 
-            let v1 = model.ensure("var1");
-            let vt = model.ensure("test");
-            let vg = model.ensure("GATA3");
-            let vt2 = model.ensure("test:2");
-            let vf = model.ensure("Foxp3");
-            let vf = model.ensure_threshold(17, 2);
-            let v1 = model.ensure("pipo");
+            let _v1 = model.ensure("var1");
+            let _vt = model.ensure("test");
+            let _vg = model.ensure("GATA3");
+            let _vt2 = model.ensure("test:2");
+            let _vf = model.ensure("Foxp3");
+            let _vf = model.ensure_threshold(17, 2).unwrap();
+            let _v1 = model.ensure("pipo");
 
             println!("{:#?}", model.get_handle("v"));
 
@@ -303,4 +710,206 @@ mod tests {
 
         println!("trap spaces: {}", fp);
     }
+
+    #[test]
+    fn rule_from_str() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        model.set_rule_from_str(a, 1, "B & !C").unwrap();
+        model.set_rule_from_str(a, 2, "B & C").unwrap();
+        assert_eq!(model.get_variables(a).len(), 2);
+        assert_eq!(format!("{}", model), "A <- (B & !C)\nA:2 <- (B & C)\n");
+
+        // Replace an existing rule
+        model.set_rule_from_str(a, 1, "C").unwrap();
+        assert_eq!(format!("{}", model), "A <- C\nA:2 <- (B & C)\n");
+        let rules = model.rules.get(a).unwrap();
+        assert!(!rules.raw_variable_formula(2).prime_implicants().is_empty());
+
+        assert!(model.set_rule_from_str(a, 1, "B &").is_err());
+        assert!(model.set_rule_from_str(42, 1, "B").is_err());
+
+        // Failed calls leave the model and its history unchanged
+        let components: Vec<usize> = model.components().copied().collect();
+        let edits = model.history().done().count();
+        assert!(model.set_rule_from_str(a, 1, "B & X & C:0").is_err());
+        assert!(model.set_rule_from_str(a, 1, "X:abc").is_err());
+        assert_eq!(model.components().copied().collect::<Vec<_>>(), components);
+        assert_eq!(model.history().done().count(), edits);
+    }
+
+    #[test]
+    fn check_levels() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        model.set_rule_from_str(a, 1, "B").unwrap();
+        assert!(model.ensure_threshold(a, 0).is_err());
+        assert!(model.ensure_threshold(a, MAX_LEVEL + 1).is_err());
+        assert_eq!(model.get_variables(a).len(), 1);
+        assert!(model.set_rule_from_str(a, MAX_LEVEL + 1, "B").is_err());
+
+        // Locked and restricted levels can not exceed the maximal level of the component
+        assert!(model.lock_component(a, 2).is_err());
+        assert!(model.restrict_component(a, 0, 2).is_err());
+        assert!(model.restrict_component(a, 1, 0).is_err());
+        assert_eq!(format!("{}", model), "A <- B\n");
+        model.lock_component(a, 1).unwrap();
+        assert_eq!(format!("{}", model), "A <- True\n");
+    }
+
+    #[test]
+    fn normalize_names() {
+        let mut model = QModel::default();
+        let a = model.ensure("a.b");
+        assert_eq!(model.get_name(a), "a_b");
+        assert_eq!(model.get_label(a), Some("a.b"));
+        assert_eq!(model.ensure("a.b"), a);
+        assert_eq!(model.get_handle("a.b"), Some(a));
+        let a2 = model.ensure("a.b:2");
+        assert_eq!(model.get_variable(a, 2), Some(a2));
+        assert_eq!(model.get_name(a2), "a_b:2");
+
+        // Collisions and invalid first characters
+        let b = model.ensure("a-b");
+        assert_eq!(model.get_name(b), "a_b_1");
+        let c = model.ensure("2 c");
+        assert_eq!(model.get_name(c), "_2_c");
+        let d = model.ensure("d:e");
+        assert_eq!(model.get_name(d), "d_e");
+        assert_eq!(model.ensure("D"), model.get_handle("D").unwrap());
+        assert!(model.get_label(model.get_handle("D").unwrap()).is_none());
+
+        // Invalid thresholds are rejected without creating anything
+        let count = model.components().len();
+        for name in &["X:0", "X:1000", "X:99999999999999999999999"] {
+            assert!(model.try_ensure(name).is_err());
+        }
+        assert_eq!(model.components().len(), count);
+        assert!(model.get_handle("X").is_none());
+
+        model.set_name_policy(NamePolicy::STRIP);
+        let e = model.ensure("e f");
+        assert_eq!(model.get_name(e), "ef");
+        model.set_name_policy(NamePolicy::GENERIC);
+        let f = model.ensure("f g");
+        let g = model.ensure("g h");
+        assert_eq!(model.get_name(f), "cpt");
+        assert_eq!(model.get_name(g), "cpt_1");
+        assert_eq!(model.labels().count(), 7);
+
+        model.remove_component(g);
+        assert!(model.get_handle("g h").is_none());
+    }
+
+    #[test]
+    fn use_normalized_names() {
+        let mut model = QModel::default();
+        let sbml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbml xmlns="http://www.sbml.org/sbml/level3/version1/core" level="3" version="1"
+      xmlns:qual="http://www.sbml.org/sbml/level3/version1/qual/version1" qual:required="true">
+  <model id="digits">
+    <qual:listOfQualitativeSpecies>
+      <qual:qualitativeSpecies qual:id="1a" qual:maxLevel="1"/>
+      <qual:qualitativeSpecies qual:id="2b_1" qual:maxLevel="1"/>
+      <qual:qualitativeSpecies qual:id="2b_2" qual:maxLevel="1"/>
+    </qual:listOfQualitativeSpecies>
+  </model>
+</sbml>"#;
+        let format = io::get_format("sbml").unwrap();
+        format
+            .as_parser()
+            .unwrap()
+            .parse_into_model(&mut model, sbml)
+            .unwrap();
+        let a = model.get_handle("1a").unwrap();
+        assert_eq!(model.get_name(a), "_1a");
+        assert!(is_valid_name("_1a"));
+
+        // Rename to and from normalized names
+        model.rename("_1a", "_1c").unwrap();
+        model.set_names(&[(a, "_1a".to_owned())]).unwrap();
+        assert_eq!(model.get_name(a), "_1a");
+
+        // Import normalized names without prefix
+        let mut other = QModel::default();
+        let d = other.ensure("3 d");
+        other.set_rule_from_str(d, 1, "_1a").unwrap();
+        let mut merger = Merger::new(&mut model);
+        merger.share("_1a");
+        assert_eq!(merger.apply(&other).unwrap(), 1);
+        assert!(model.get_handle("_3_d").is_some());
+
+        // Regroup the Boolean encoding of a normalized name
+        let mut groups = ThresholdGroups::new(&mut model);
+        assert_eq!(groups.detect(), 1);
+        groups.apply().unwrap();
+        let b = model.get_handle("_2b").unwrap();
+        assert_eq!(model.get_variables(b).len(), 2);
+    }
+
+    #[test]
+    fn remove_and_reorder() {
+        let mut model = QModel::default();
+        let c = model.ensure("C");
+        let b = model.ensure("B");
+        let a = model.ensure("A");
+        model.set_rule_from_str(a, 1, "C:3").unwrap();
+        model.set_rule_from_str(b, 1, "A").unwrap();
+        model.set_rule_from_str(c, 1, "B").unwrap();
+        model.set_rule_from_str(c, 2, "B & A").unwrap();
+        model.set_rule_from_str(c, 3, "A").unwrap();
+        let c2 = model.get_variable(c, 2).unwrap();
+        let c3 = model.get_variable(c, 3).unwrap();
+        assert_eq!(model.variables().len(), 5);
+        assert_eq!(model.get_handle("C:3"), Some(c3));
+
+        // The removed variable must not be used
+        assert!(model.remove_threshold(c, 3).is_err());
+        assert!(model.remove_threshold(c, 1).is_err());
+        model.remove_threshold(c, 2).unwrap();
+        assert_eq!(model.get_variables(c), &vec![c, c3]);
+        assert_eq!(model.get_name(c3), "C:2");
+        assert_eq!(model.get_handle("C:2"), Some(c3));
+        assert!(model.get_component_value(c2).is_none());
+        assert_eq!(
+            format!("{}", model),
+            "C <- B\nC <- (B & A)\nC:2 <- A\nB <- A\nA <- C:2\n"
+        );
+
+        model.sort_components_by_name();
+        assert_eq!(
+            model.components().copied().collect::<Vec<_>>(),
+            vec![a, b, c]
+        );
+        assert_eq!(
+            model.variables().copied().collect::<Vec<_>>(),
+            vec![a, b, c, c3]
+        );
+        model.sort_components_by_graph();
+        assert_eq!(
+            model.components().copied().collect::<Vec<_>>(),
+            vec![a, b, c]
+        );
+        model.set_rule_from_str(a, 1, "1").unwrap();
+        model.sort_components_by_graph();
+        assert_eq!(
+            model.components().copied().collect::<Vec<_>>(),
+            vec![a, b, c]
+        );
+        model.set_rule_from_str(b, 1, "C").unwrap();
+        model.set_rule_from_str(c, 1, "A").unwrap();
+        model.sort_components_by_graph();
+        assert_eq!(
+            model.components().copied().collect::<Vec<_>>(),
+            vec![a, c, b]
+        );
+
+        assert!(model.reorder_components(&[a, b]).is_err());
+        assert!(model.reorder_components(&[a, b, b]).is_err());
+        model.undo();
+        assert_eq!(
+            model.components().copied().collect::<Vec<_>>(),
+            vec![a, b, c]
+        );
+    }
 }
@@ -0,0 +1,335 @@
+//! Report potential problems in a model.
+//!
+//! Each diagnostic is associated to a component and a severity:
+//! * errors denote inconsistent models, for example rules using removed components;
+//! * warnings denote suspicious constructs, which are valid but likely unintended;
+//! * infos denote notable properties of the model, like constant components or self-loops.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::func::expr::Expr;
+use crate::func::monotonicity::Effect;
+use crate::func::Formula;
+use crate::helper::error::GenericError;
+use crate::model::rule::ComponentRules;
+use crate::model::{GroupedVariables, QModel};
+
+/// Importance of a diagnostic, from the least to the most severe
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    INFO,
+    WARNING,
+    ERROR,
+}
+
+/// Category of problem reported by a diagnostic
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Lint {
+    /// A rule uses a variable which is not defined in the model
+    UNKNOWN,
+    /// A buffer mirroring a component which is no longer in the model
+    ORPHAN,
+    /// A component without any rule
+    MISSING,
+    /// An assignment to a level is overridden by a later assignment to a lower level
+    OVERRIDDEN,
+    /// The condition written for a level does not imply the condition written for a lower level
+    NONMONOTONIC,
+    /// A regulator appears in a rule without affecting it
+    NONFUNCTIONAL,
+    /// A threshold which is not used in any rule
+    UNUSED,
+    /// A component whose rules do not depend on any regulator
+    CONSTANT,
+    /// A component regulating itself
+    SELFLOOP,
+}
+
+/// A problem found in a model
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub lint: Lint,
+    /// Name of the component associated to this problem
+    pub component: String,
+    pub message: String,
+}
+
+impl Lint {
+    pub fn severity(self) -> Severity {
+        match self {
+            Lint::UNKNOWN => Severity::ERROR,
+            Lint::ORPHAN
+            | Lint::MISSING
+            | Lint::OVERRIDDEN
+            | Lint::NONMONOTONIC
+            | Lint::NONFUNCTIONAL
+            | Lint::UNUSED => Severity::WARNING,
+            Lint::CONSTANT | Lint::SELFLOOP => Severity::INFO,
+        }
+    }
+}
+
+impl Diagnostic {
+    fn new(model: &QModel, cid: usize, lint: Lint, message: String) -> Self {
+        Diagnostic {
+            severity: lint.severity(),
+            lint,
+            component: model.get_name(cid).to_owned(),
+            message,
+        }
+    }
+}
+
+/// Check all components of a model, the diagnostics are sorted by decreasing severity
+pub fn check_model(model: &QModel) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut used = HashSet::new();
+    for cid in model.components() {
+        check_component(model, *cid, &mut used, &mut diagnostics);
+    }
+
+    for cid in model.components() {
+        for (vid, value) in model.get_variables(*cid).iter().zip(1..).skip(1) {
+            if !used.contains(vid) {
+                let message = format!("{}:{} is not used in any rule", model.get_name(*cid), value);
+                diagnostics.push(Diagnostic::new(model, *cid, Lint::UNUSED, message));
+            }
+        }
+    }
+
+    diagnostics.sort_by_key(|d| Reverse(d.severity));
+    diagnostics
+}
+
+fn check_component(
+    model: &QModel,
+    cid: usize,
+    used: &mut HashSet<usize>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let name = model.get_name(cid);
+    let rules = match model.rules.get(cid) {
+        Some(r) if r.assignments().len() > 0 => r,
+        _ => {
+            let message = format!("{} has no rule", name);
+            diagnostics.push(Diagnostic::new(model, cid, Lint::MISSING, message));
+            return;
+        }
+    };
+
+    // Collect the regulators written in the rules
+    let conditions: Vec<(usize, Rc<Expr>)> = rules
+        .assignments()
+        .map(|a| (a.target, a.convert()))
+        .collect();
+    let mut regulators = HashSet::new();
+    for (_, e) in conditions.iter() {
+        let literals = e.get_literals();
+        regulators.extend(literals.positive().union(literals.negative()));
+    }
+    // Rules which mirror a removed component are reported once, as orphans
+    let orphaned = mirrored_variables(rules).filter(|mirrored| {
+        mirrored
+            .iter()
+            .all(|vid| model.get_component_value(*vid).is_none())
+    });
+    for vid in regulators.iter() {
+        used.insert(*vid);
+        if model.get_component_value(*vid).is_none()
+            && !orphaned.as_ref().is_some_and(|m| m.contains(vid))
+        {
+            let message = format!("The rules of {} use an unknown variable", name);
+            diagnostics.push(Diagnostic::new(model, cid, Lint::UNKNOWN, message));
+        }
+    }
+    if orphaned.is_some() {
+        let message = format!(
+            "{} mirrors a component which is no longer in the model",
+            name
+        );
+        diagnostics.push(Diagnostic::new(model, cid, Lint::ORPHAN, message));
+    }
+
+    // Later assignments to lower levels take precedence over the previous ones
+    for (idx, (target, e)) in conditions.iter().enumerate() {
+        for (other, o) in conditions.iter().skip(idx + 1) {
+            if other < target && !e.and(o).prime_implicants().is_empty() {
+                let message = format!(
+                    "The assignment of {} to level {} is overridden by a later assignment to level {}",
+                    name, target, other
+                );
+                diagnostics.push(Diagnostic::new(model, cid, Lint::OVERRIDDEN, message));
+            }
+        }
+    }
+
+    // The per-level formulas derived from the rules are nested by construction (the activation
+    // of a level implies the activation of the lower ones), check the written conditions instead
+    let mut targets: BTreeMap<usize, Expr> = BTreeMap::new();
+    for (target, e) in conditions.iter() {
+        let cur = targets.entry(*target).or_insert(Expr::FALSE);
+        *cur = cur.or(e);
+    }
+    for ((lower, l), (higher, h)) in targets.iter().zip(targets.iter().skip(1)) {
+        if !h.and(&l.not()).prime_implicants().is_empty() {
+            let message = format!(
+                "The condition for level {} of {} does not imply the condition for level {}",
+                higher, name, lower
+            );
+            diagnostics.push(Diagnostic::new(model, cid, Lint::NONMONOTONIC, message));
+        }
+    }
+
+    // Identify the effect of each regulator over all thresholds
+    let max = model.get_variables(cid).len();
+    let mut effects: BTreeMap<usize, Effect> = BTreeMap::new();
+    let mut levels = Some(0);
+    for value in 1..=max {
+        let e = rules.raw_variable_formula(value);
+        if e.not().prime_implicants().is_empty() {
+            levels = levels.map(|l| l + 1);
+        } else if !e.prime_implicants().is_empty() {
+            levels = None;
+        }
+        for (vid, effect) in Formula::from(e).regulator_effects() {
+            let cur = effects.entry(vid).or_insert(Effect::NONE);
+            *cur = cur.merge(effect);
+        }
+    }
+    for vid in regulators.iter() {
+        let effect = effects.get(vid).copied().unwrap_or(Effect::NONE);
+        if effect.is_functional() || model.get_component_value(*vid).is_none() {
+            continue;
+        }
+        let message = format!(
+            "{} appears in the rules of {} without effect",
+            model.get_name(*vid),
+            name
+        );
+        diagnostics.push(Diagnostic::new(model, cid, Lint::NONFUNCTIONAL, message));
+    }
+
+    if let Some(level) = levels {
+        let message = format!("{} is fixed at level {}", name, level);
+        diagnostics.push(Diagnostic::new(model, cid, Lint::CONSTANT, message));
+    }
+    let self_loop = effects.iter().any(|(vid, effect)| {
+        effect.is_functional() && model.get_component_value(*vid).map(|v| v.component) == Some(cid)
+    });
+    if self_loop {
+        let message = format!("{} regulates itself", name);
+        diagnostics.push(Diagnostic::new(model, cid, Lint::SELFLOOP, message));
+    }
+}
+
+/// Variables copied by a buffer component, as created by the buffering modifier:
+/// each level is assigned in order to a single variable of the source component
+fn mirrored_variables(rules: &ComponentRules) -> Option<Vec<usize>> {
+    rules
+        .assignments()
+        .zip(1..)
+        .map(|(a, value)| {
+            if a.target != value {
+                return None;
+            }
+            match *a.convert::<Expr>() {
+                Expr::ATOM(vid) => Some(vid),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+impl FromStr for Severity {
+    type Err = GenericError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "info" => Ok(Severity::INFO),
+            "warning" => Ok(Severity::WARNING),
+            "error" => Ok(Severity::ERROR),
+            _ => Err(GenericError::new(format!("Unknown severity: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::INFO => write!(f, "info"),
+            Severity::WARNING => write!(f, "warning"),
+            Severity::ERROR => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: [{}] {}",
+            self.severity, self.component, self.message
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::func::Formula;
+    use crate::model::actions::check::{check_model, Lint, Severity};
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn check_model_lints() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        let d = model.ensure("D");
+        model.set_rule_from_str(a, 1, "A & B | A & !B").unwrap();
+        model.set_rule_from_str(b, 1, "C").unwrap();
+        model.set_rule_from_str(b, 2, "C & !A").unwrap();
+        model.set_rule_from_str(c, 1, "true").unwrap();
+        model.set_rule_from_str(d, 2, "A").unwrap();
+        model.push_cpt_rule(d, 1, Formula::from(Expr::ATOM(c)));
+        // Buffers are identified by their mirror rules, not by their name.
+        // Orphaned buffers are not reported as unknown variables
+        let buffer = model.ensure("copy");
+        model.set_rule_from_str(buffer, 1, "X").unwrap();
+        let named = model.ensure("_b_Y");
+        model.set_rule_from_str(named, 1, "A & C | X").unwrap();
+        model.remove_component(model.get_handle("X").unwrap());
+
+        let diagnostics = check_model(&model);
+        let found: Vec<(Lint, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.lint, d.component.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Lint::UNKNOWN, "_b_Y"),
+                (Lint::NONFUNCTIONAL, "A"),
+                (Lint::OVERRIDDEN, "D"),
+                (Lint::NONMONOTONIC, "D"),
+                (Lint::ORPHAN, "copy"),
+                (Lint::UNUSED, "B"),
+                (Lint::UNUSED, "D"),
+                (Lint::SELFLOOP, "A"),
+                (Lint::CONSTANT, "C"),
+            ]
+        );
+        assert_eq!(diagnostics[0].severity, Severity::ERROR);
+        assert_eq!(
+            format!("{}", diagnostics[1]),
+            "warning: [A] B appears in the rules of A without effect"
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Compare two models.
+//!
+//! Components are matched by name. The rules of components found in both models are compared
+//! semantically: two rules differ only if they denote different Boolean functions, regardless
+//! of how they are written.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::func::expr::{AtomReplacer, Expr};
+use crate::func::monotonicity::Effect;
+use crate::model::layout::NodeLayoutInfo;
+use crate::model::QModel;
+use crate::variables::GroupedVariables;
+
+/// An interaction between named components
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamedInteraction {
+    pub source: String,
+    pub threshold: usize,
+    pub target: String,
+    pub effect: Effect,
+}
+
+/// Differences between an original model and a new version
+#[derive(Clone, Debug, Default)]
+pub struct ModelDiff {
+    /// Components only found in the new model
+    pub added: Vec<String>,
+    /// Components only found in the original model
+    pub removed: Vec<String>,
+    /// Components with a different max level: name, original and new levels
+    pub max_levels: Vec<(String, usize, usize)>,
+    /// Thresholds of common components with semantically different rules
+    pub rules: Vec<(String, usize)>,
+    /// Interactions only found in the new model
+    pub gained_edges: Vec<NamedInteraction>,
+    /// Interactions only found in the original model
+    pub lost_edges: Vec<NamedInteraction>,
+    /// Interactions with a different effect: original interaction and new effect
+    pub changed_edges: Vec<(NamedInteraction, Effect)>,
+    /// Components with a different layout: name, original and new bounding boxes
+    pub layout: Vec<(String, Option<NodeLayoutInfo>, Option<NodeLayoutInfo>)>,
+}
+
+/// Map the variables of the new model to the variables of the original model
+struct Translation {
+    mapping: HashMap<usize, usize>,
+}
+
+impl AtomReplacer for Translation {
+    fn replace(&mut self, var: usize, value: bool) -> Option<Expr> {
+        self.mapping.get(&var).map(|v| {
+            if value {
+                Expr::ATOM(*v)
+            } else {
+                Expr::NATOM(*v)
+            }
+        })
+    }
+}
+
+impl Translation {
+    /// Variables missing in the original model are given fresh identifiers
+    fn new(original: &QModel, model: &QModel) -> Self {
+        let offset = original
+            .components()
+            .flat_map(|cid| original.get_variables(*cid).iter())
+            .max()
+            .map(|v| v + 1)
+            .unwrap_or(0);
+        let mut mapping = HashMap::new();
+        let variables = model
+            .components()
+            .flat_map(|cid| model.get_variables(*cid).iter());
+        for vid in variables {
+            let var = match model.get_component_value(*vid) {
+                None => continue,
+                Some(v) => v,
+            };
+            let target = original
+                .get_handle(model.get_name(var.component))
+                .and_then(|cid| original.get_variable(cid, var.value))
+                .unwrap_or(offset + vid);
+            mapping.insert(*vid, target);
+        }
+        Translation { mapping }
+    }
+
+    fn translate(&mut self, e: Expr) -> Expr {
+        e.replace_variables(self).unwrap_or(e)
+    }
+}
+
+impl ModelDiff {
+    pub fn new(original: &QModel, model: &QModel) -> Self {
+        let mut diff = ModelDiff::default();
+        let mut translation = Translation::new(original, model);
+
+        for cid in original.components() {
+            let name = original.get_name(*cid);
+            let other = match model.get_handle(name) {
+                None => {
+                    diff.removed.push(name.to_owned());
+                    continue;
+                }
+                Some(c) => c,
+            };
+
+            let max = original.get_variables(*cid).len();
+            let other_max = model.get_variables(other).len();
+            if max != other_max {
+                diff.max_levels.push((name.to_owned(), max, other_max));
+            }
+
+            for value in 1..=max.min(other_max) {
+                let e = component_rule(original, *cid, value);
+                let other_e = translation.translate(component_rule(model, other, value));
+                if !e.is_equivalent(&other_e) {
+                    diff.rules.push((name.to_owned(), value));
+                }
+            }
+
+            let bb = original.get_bounding_box(*cid).copied();
+            let other_bb = model.get_bounding_box(other).copied();
+            if bb != other_bb {
+                diff.layout.push((name.to_owned(), bb, other_bb));
+            }
+        }
+
+        diff.added = model
+            .components()
+            .map(|cid| model.get_name(*cid))
+            .filter(|name| original.get_handle(name).is_none())
+            .map(str::to_owned)
+            .collect();
+
+        let edges = named_interactions(original);
+        let other_edges = named_interactions(model);
+        for edge in edges.iter() {
+            match other_edges.iter().find(|e| e.same_link(edge)) {
+                None => diff.lost_edges.push(edge.clone()),
+                Some(e) if e.effect != edge.effect => {
+                    diff.changed_edges.push((edge.clone(), e.effect))
+                }
+                _ => (),
+            }
+        }
+        diff.gained_edges = other_edges
+            .into_iter()
+            .filter(|e| !edges.iter().any(|edge| edge.same_link(e)))
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.max_levels.is_empty()
+            && self.rules.is_empty()
+            && self.gained_edges.is_empty()
+            && self.lost_edges.is_empty()
+            && self.changed_edges.is_empty()
+            && self.layout.is_empty()
+    }
+}
+
+impl NamedInteraction {
+    fn same_link(&self, other: &NamedInteraction) -> bool {
+        self.source == other.source
+            && self.threshold == other.threshold
+            && self.target == other.target
+    }
+
+    fn fmt_link(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)?;
+        if self.threshold != 1 {
+            write!(f, ":{}", self.threshold)?;
+        }
+        write!(f, " -> {}", self.target)
+    }
+}
+
+fn component_rule(model: &QModel, cid: usize, value: usize) -> Expr {
+    model
+        .rules
+        .get(cid)
+        .map(|r| r.raw_variable_formula(value))
+        .unwrap_or(Expr::FALSE)
+}
+
+fn named_interactions(model: &QModel) -> Vec<NamedInteraction> {
+    model
+        .interaction_graph()
+        .iter()
+        .map(|i| NamedInteraction {
+            source: model.get_name(i.source).to_owned(),
+            threshold: i.threshold,
+            target: model.get_name(i.target).to_owned(),
+            effect: i.effect,
+        })
+        .collect()
+}
+
+impl fmt::Display for NamedInteraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_link(f)?;
+        write!(f, " ({})", self.effect)
+    }
+}
+
+impl fmt::Display for ModelDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in &self.added {
+            writeln!(f, "+ component {}", name)?;
+        }
+        for name in &self.removed {
+            writeln!(f, "- component {}", name)?;
+        }
+        for (name, max, other) in &self.max_levels {
+            writeln!(f, "~ max level {}: {} -> {}", name, max, other)?;
+        }
+        for (name, value) in &self.rules {
+            if *value == 1 {
+                writeln!(f, "~ rule {}", name)?;
+            } else {
+                writeln!(f, "~ rule {}:{}", name, value)?;
+            }
+        }
+        for edge in &self.gained_edges {
+            writeln!(f, "+ edge {}", edge)?;
+        }
+        for edge in &self.lost_edges {
+            writeln!(f, "- edge {}", edge)?;
+        }
+        for (edge, effect) in &self.changed_edges {
+            write!(f, "~ edge ")?;
+            edge.fmt_link(f)?;
+            writeln!(f, " ({} -> {})", edge.effect, effect)?;
+        }
+        for (name, bb, other) in &self.layout {
+            write!(f, "~ layout {}: ", name)?;
+            match bb {
+                None => write!(f, "none")?,
+                Some(bb) => write!(f, "{}", bb)?,
+            }
+            match other {
+                None => writeln!(f, " -> none")?,
+                Some(bb) => writeln!(f, " -> {}", bb)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::monotonicity::Effect;
+    use crate::model::actions::diff::ModelDiff;
+    use crate::model::layout::NodeLayoutInfo;
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn compare_models() {
+        let mut original = QModel::default();
+        let a = original.ensure("A");
+        let b = original.ensure("B");
+        let c = original.ensure("C");
+        original.set_rule_from_str(a, 1, "B & C | B & !C").unwrap();
+        original.set_rule_from_str(b, 1, "!A").unwrap();
+        original.set_rule_from_str(c, 1, "A").unwrap();
+        assert!(ModelDiff::new(&original, &original).is_empty());
+
+        // Declare the components in a different order and rewrite equivalent rules
+        let mut model = QModel::default();
+        let d = model.ensure("D");
+        let c = model.ensure("C");
+        let b = model.ensure("B");
+        let a = model.ensure("A");
+        model.set_rule_from_str(a, 1, "B").unwrap();
+        model.set_rule_from_str(b, 1, "A & D").unwrap();
+        model.set_rule_from_str(c, 1, "A").unwrap();
+        model.set_rule_from_str(c, 2, "A & B").unwrap();
+        model.set_rule_from_str(d, 1, "C:2").unwrap();
+        let bb = NodeLayoutInfo {
+            x: 1,
+            y: 2,
+            width: 3,
+            height: 4,
+        };
+        model.set_bounding_box(a, bb);
+
+        let diff = ModelDiff::new(&original, &model);
+        assert_eq!(diff.added, vec!["D"]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.max_levels, vec![("C".to_owned(), 1, 2)]);
+        assert_eq!(diff.rules, vec![("B".to_owned(), 1)]);
+        assert_eq!(diff.gained_edges.len(), 3);
+        assert!(diff.lost_edges.is_empty());
+        assert_eq!(diff.changed_edges.len(), 1);
+        assert_eq!(diff.changed_edges[0].0.source, "A");
+        assert_eq!(diff.changed_edges[0].1, Effect::POSITIVE);
+        assert_eq!(diff.layout, vec![("A".to_owned(), None, Some(bb))]);
+
+        let reverse = ModelDiff::new(&model, &original);
+        assert_eq!(reverse.removed, vec!["D"]);
+        assert_eq!(reverse.lost_edges.len(), 3);
+    }
+}
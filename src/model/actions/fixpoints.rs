@@ -2,22 +2,32 @@ use std::fmt;
 
 use itertools::Itertools;
 
+use crate::func::cnf::CNF;
 use crate::func::expr::Expr;
 use crate::func::pattern::Pattern;
 use crate::func::Formula;
-use crate::helper::error::EmptyLomakResult;
+use crate::helper::error::{EmptyLomakResult, GenericError, LomakResult};
 use crate::helper::solver;
 use crate::helper::solver::SolverMode;
 use crate::model::{GroupedVariables, SharedModel};
 use crate::variables::ModelVariables;
 use std::collections::HashMap;
 use std::fmt::Formatter;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::Command;
 use std::rc::Rc;
+use std::{env, fs, process};
+
+/// Default limit on the number of fixed points enumerated with an external SAT solver
+pub const MAX_SAT_SOLUTIONS: usize = 10000;
 
 pub struct FixedBuilder {
     variables: Rc<ModelVariables>,
     rules: Rc<HashMap<usize, Formula>>,
     restriction: Option<Pattern>,
+    enforced: Pattern,
 }
 
 pub struct FixedPoints {
@@ -33,6 +43,7 @@ impl FixedBuilder {
             variables: m.frozen_variables(),
             rules: m.frozen_rules(),
             restriction: None,
+            enforced: Pattern::new(),
         }
     }
 
@@ -51,6 +62,11 @@ impl FixedBuilder {
         self.restriction.as_mut().unwrap().set(uid, value);
     }
 
+    /// Only search fixed points contained in a pattern
+    pub fn enforce(&mut self, p: &Pattern) {
+        self.enforced.add_constraints_from(p);
+    }
+
     pub fn solve(&self, max: Option<usize>) -> FixedPoints {
         let mut solver = solver::get_solver(SolverMode::ALL);
 
@@ -64,37 +80,107 @@ impl FixedBuilder {
         println!("#VARS: {{{}}}.", s);
         solver.add(&s);
 
-        // For each variable:
-        //   * retrieve the Boolean formula
-        //   * derive the stability condition
-        //   * encode it in ASP
-        for vid in self.variables.variables() {
-            let cur = Expr::ATOM(*vid);
-            // TODO: handle missing expr ??
-            let e: Rc<Expr> = self.rules.get(vid).map(|f| f.convert_as()).unwrap();
-            for p in cur.not().and(&e).prime_implicants().iter() {
-                solver.restrict(p);
-            }
-            for p in cur.and(&e.not()).prime_implicants().iter() {
-                solver.restrict(p);
-            }
-        }
-
-        // Add additional restrictions
-        if self.restriction.is_some() {
-            solver.restrict(self.restriction.as_ref().unwrap());
+        for p in self.forbidden_patterns() {
+            solver.restrict(&p);
         }
 
         // Extract patterns from the clingo results
         let patterns = solver
             .solve()
-            .into_iter()
             .map(|r| r.into_pattern())
             .take(max.unwrap_or(10000))
             .collect_vec();
 
         FixedPoints::new(Rc::clone(&self.variables), patterns)
     }
+
+    /// List the patterns which can not contain any fixed point.
+    ///
+    /// For each variable, the stability condition is violated in the prime implicants
+    /// of ``!v & f`` and ``v & !f``. Additional restrictions and the opposite of
+    /// enforced values are also excluded.
+    fn forbidden_patterns(&self) -> Vec<Pattern> {
+        let mut result = vec![];
+        for vid in self.variables.variables() {
+            let cur = Expr::ATOM(*vid);
+            // TODO: handle missing expr ??
+            let e: Rc<Expr> = self.rules.get(vid).map(|f| f.convert_as()).unwrap();
+            result.extend(cur.not().and(&e).prime_implicants().iter().cloned());
+            result.extend(cur.and(&e.not()).prime_implicants().iter().cloned());
+        }
+
+        if let Some(restriction) = &self.restriction {
+            result.push(restriction.clone());
+        }
+        for uid in self.enforced.positive().iter() {
+            result.push(Pattern::with(uid, false));
+        }
+        for uid in self.enforced.negative().iter() {
+            result.push(Pattern::with(uid, true));
+        }
+        result
+    }
+
+    /// Encode the search for fixed points as a SAT problem in conjunctive normal form
+    pub fn to_cnf(&self) -> CNF {
+        let mut cnf = CNF::new();
+        // Allocate the model variables first to keep them at the start of the DIMACS numbering
+        for vid in self.variables.variables() {
+            cnf.literal(*vid, true);
+        }
+        for p in self.forbidden_patterns() {
+            cnf.forbid(&p);
+        }
+        cnf
+    }
+
+    /// Search the fixed points using an external SAT solver instead of clingo.
+    ///
+    /// The solver command is called with the path of a DIMACS file, it should print its result
+    /// on the standard output and exit with the usual codes: 10 (SAT) or 20 (UNSAT). Solvers
+    /// writing their result to a file, like MiniSat, need a wrapper script. Each solution is
+    /// excluded from the next call. Without a maximum, the search stops after
+    /// ``MAX_SAT_SOLUTIONS`` solutions.
+    pub fn solve_sat(&self, command: &str, max: Option<usize>) -> LomakResult<FixedPoints> {
+        let mut cnf = self.to_cnf();
+        let file = TempFile(env::temp_dir().join(format!("lomak-{}.cnf", process::id())));
+        let mut patterns = vec![];
+        while patterns.len() < max.unwrap_or(MAX_SAT_SOLUTIONS) {
+            let mut writer = BufWriter::new(File::create(&file.0)?);
+            cnf.write_dimacs(&mut writer)?;
+            writer.flush()?;
+            drop(writer);
+            let output = Command::new(command).arg(&file.0).output()?;
+            if !matches!(output.status.code(), Some(10) | Some(20)) {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(GenericError::new(format!(
+                    "The SAT solver failed ({}): {}",
+                    output.status,
+                    stderr.trim()
+                ))
+                .into());
+            }
+            let text = String::from_utf8_lossy(&output.stdout);
+            match cnf.parse_solution(&text)? {
+                None => break,
+                Some(p) => {
+                    cnf.forbid(&p);
+                    patterns.push(p);
+                }
+            }
+        }
+
+        Ok(FixedPoints::new(Rc::clone(&self.variables), patterns))
+    }
+}
+
+/// Temporary file removed when it goes out of scope
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
 }
 
 impl FixedPoints {
@@ -107,35 +193,39 @@ impl FixedPoints {
     }
 
     pub fn set_displayed_names(&mut self, names: Option<Vec<String>>) {
-        self.displayed = match names {
-            None => None,
-            Some(n) => Some(
-                n.iter()
-                    .filter_map(|s| self.variables.get_handle(s))
-                    .collect(),
-            ),
-        };
+        self.displayed = names.map(|n| {
+            n.iter()
+                .filter_map(|s| self.variables.get_handle(s))
+                .collect()
+        });
     }
 
     pub fn set_displayed(&mut self, displayed: Option<Vec<usize>>) {
         self.displayed = displayed;
     }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
 }
 
 impl fmt::Display for FixedPoints {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        // TODO: filter displayed components
+        let uids = match &self.displayed {
+            Some(d) => d.clone(),
+            None => self.variables.variables().copied().collect(),
+        };
         writeln!(
             f,
             "{}",
-            self.variables
-                .variables()
+            uids.iter()
                 .map(|uid| self.variables.get_name(*uid))
                 .join(" ")
         )?;
 
         for p in self.patterns.iter() {
-            writeln!(f, "{}", p)?;
+            p.filter_fmt(f, &uids)?;
+            writeln!(f)?;
         }
         write!(f, "")
     }
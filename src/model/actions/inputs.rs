@@ -0,0 +1,148 @@
+//! Fix the level of input components for the analysis of a model.
+//!
+//! Inputs can be fixed at selected levels (``A=1``) or all combinations of levels of the
+//! free inputs can be enumerated (``all``). Each input condition yields a copy of the model
+//! in which the corresponding inputs are locked, analysis results can then be grouped by
+//! input condition.
+
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::func::{Grouped, VariableNamer};
+use crate::helper::error::GenericError;
+use crate::model::graph::InputKind;
+use crate::model::QModel;
+use crate::variables::{parse_level_assignment, GroupedVariables};
+
+/// Selected levels of a group of input components
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct InputCondition {
+    levels: Vec<(usize, usize)>,
+}
+
+impl InputCondition {
+    /// Pairs of input components and levels
+    pub fn levels(&self) -> &[(usize, usize)] {
+        &self.levels
+    }
+
+    /// Check if this condition does not fix any input
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// Copy the model and lock all inputs at the selected levels
+    pub fn apply(&self, model: &QModel) -> QModel {
+        let mut result = QModel::clone(model);
+        for (cid, level) in self.levels.iter() {
+            result
+                .lock_component(*cid, *level)
+                .expect("Input levels are checked when listing conditions");
+        }
+        result
+    }
+}
+
+/// List the input conditions matching a selection of input levels.
+///
+/// Each element of the selection fixes the level of an input (``A=1``), or enumerates all
+/// combinations of levels for the free inputs which are not fixed (``all``).
+pub fn input_conditions(
+    model: &QModel,
+    selection: &[String],
+) -> Result<Vec<InputCondition>, GenericError> {
+    let mut fixed = vec![];
+    let mut enumerate = false;
+    for s in selection.iter() {
+        if s.trim() == "all" {
+            enumerate = true;
+            continue;
+        }
+        let (cid, level) = parse_level_assignment(model, s)?;
+        if model.input_kind(cid).is_none() {
+            return Err(GenericError::new(format!(
+                "{} is not an input",
+                model.get_name(cid)
+            )));
+        }
+        fixed.push((cid, level));
+    }
+
+    if !enumerate {
+        return Ok(vec![InputCondition { levels: fixed }]);
+    }
+    let free: Vec<usize> = model
+        .inputs()
+        .into_iter()
+        .filter(|cid| model.input_kind(*cid) == Some(InputKind::FREE))
+        .filter(|cid| !fixed.iter().any(|(c, _)| c == cid))
+        .collect();
+    if free.is_empty() {
+        return Ok(vec![InputCondition { levels: fixed }]);
+    }
+    let conditions = free
+        .iter()
+        .map(|cid| 0..=model.get_variables(*cid).len())
+        .multi_cartesian_product()
+        .map(|levels| {
+            let mut condition = fixed.clone();
+            condition.extend(free.iter().copied().zip(levels));
+            InputCondition { levels: condition }
+        })
+        .collect();
+    Ok(conditions)
+}
+
+impl Grouped for InputCondition {
+    fn gfmt(&self, namer: &dyn VariableNamer, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, (cid, level)) in self.levels.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+            namer.format_name(f, *cid)?;
+            write!(f, "={}", level)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::actions::inputs::input_conditions;
+    use crate::model::graph::InputKind;
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn enumerate_inputs() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        model.set_rule_from_str(a, 1, "A").unwrap();
+        model.set_rule_from_str(a, 2, "A:2").unwrap();
+        model.set_rule_from_str(b, 1, "B").unwrap();
+        model.set_rule_from_str(c, 1, "A:2 & B").unwrap();
+
+        let all = vec!["all".to_owned()];
+        let conditions = input_conditions(&model, &all).unwrap();
+        assert_eq!(conditions.len(), 6);
+        assert_eq!(conditions[5].levels(), &[(a, 2), (b, 1)]);
+
+        let selected = vec!["B=0".to_owned(), "all".to_owned()];
+        let conditions = input_conditions(&model, &selected).unwrap();
+        assert_eq!(conditions.len(), 3);
+        assert!(conditions.iter().all(|c| c.levels()[0] == (b, 0)));
+
+        // The copy locks the inputs, the original model is unchanged
+        let fixed = conditions[2].apply(&model);
+        assert_eq!(fixed.input_kind(a), Some(InputKind::FIXED(2)));
+        assert_eq!(fixed.input_kind(b), Some(InputKind::FIXED(0)));
+        assert_eq!(model.input_kind(a), Some(InputKind::FREE));
+
+        assert!(input_conditions(&model, &["C=1".to_owned()]).is_err());
+        assert!(input_conditions(&model, &["B=2".to_owned()]).is_err());
+        assert!(input_conditions(&model, &["B".to_owned()]).is_err());
+    }
+}
@@ -1,3 +1,7 @@
+pub mod check;
+pub mod diff;
 pub mod fixpoints;
+pub mod inputs;
 pub mod reach;
+pub mod stats;
 pub mod trapspaces;
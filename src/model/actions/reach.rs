@@ -80,7 +80,7 @@ fn extend(
     frozen: &BitSet,
 ) -> Pattern {
     // Compute the enclosing trapspace by extending the initial state as much as possible
-    let mut enclosing = Pattern::from_state(&initial, variables.iter());
+    let mut enclosing = Pattern::from_state(initial, variables.iter());
     let mut changed = true;
     while changed {
         changed = false;
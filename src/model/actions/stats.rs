@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+
+use crate::func::counting::FunctionStats;
+use crate::func::Formula;
+use crate::helper::error::GenericError;
+use crate::model::{GroupedVariables, QModel};
+
+/// Global statistics on a model
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelStats {
+    /// Number of components
+    pub components: usize,
+    /// Number of Boolean variables associated to component thresholds
+    pub variables: usize,
+    /// Number of functional interactions, including self-loops
+    pub edges: usize,
+    /// Number of components which are not regulated by any other component
+    pub inputs: usize,
+}
+
+impl ModelStats {
+    pub fn new(model: &QModel) -> Self {
+        let graph = model.interaction_graph();
+        let regulated: HashSet<usize> = graph
+            .iter()
+            .filter(|i| i.source != i.target)
+            .map(|i| i.target)
+            .collect();
+
+        ModelStats {
+            components: model.components().len(),
+            variables: model
+                .components()
+                .map(|cid| model.get_variables(*cid).len())
+                .sum(),
+            edges: graph.len(),
+            inputs: model
+                .components()
+                .filter(|cid| !regulated.contains(cid))
+                .count(),
+        }
+    }
+}
+
+/// Compute the statistics of the Boolean function controlling each variable of a model
+pub fn function_stats(model: &QModel) -> Result<Vec<(usize, FunctionStats)>, GenericError> {
+    model
+        .components()
+        .flat_map(|cid| model.get_variables(*cid).iter())
+        .map(|vid| Ok((*vid, Formula::from(model.get_var_rule(*vid)).stats()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::actions::stats::{function_stats, ModelStats};
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn model_stats() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        model.set_rule_from_str(a, 1, "A").unwrap();
+        model.set_rule_from_str(b, 1, "A | C").unwrap();
+        model.set_rule_from_str(c, 1, "!B").unwrap();
+        model.set_rule_from_str(c, 2, "A & !B").unwrap();
+
+        let stats = ModelStats::new(&model);
+        assert_eq!(stats.components, 3);
+        assert_eq!(stats.variables, 4);
+        assert_eq!(stats.edges, 5);
+        assert_eq!(stats.inputs, 1);
+
+        let functions = function_stats(&model).unwrap();
+        assert_eq!(functions.len(), 4);
+        assert_eq!(functions[1].0, b);
+        assert_eq!(functions[1].1.count, 3);
+    }
+}
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use itertools::Itertools;
 
 use crate::func::expr::Expr;
+use crate::func::pattern::Pattern;
 use crate::func::Formula;
 use crate::helper::solver;
 use crate::helper::solver::Solver;
@@ -32,10 +33,21 @@ impl TrapspacesBuilder {
         }
     }
 
+    /// Only retain trapspaces in which a variable is fixed at a given value
     pub fn filter(&mut self, uid: usize, b: bool) {
         self.filters.insert(uid, b);
     }
 
+    /// Only retain trapspaces contained in a pattern
+    pub fn filter_pattern(&mut self, p: &Pattern) {
+        for uid in p.positive().iter() {
+            self.filter(uid, true);
+        }
+        for uid in p.negative().iter() {
+            self.filter(uid, false);
+        }
+    }
+
     pub fn set_percolate(&mut self, b: bool) -> &mut Self {
         self.percolate = b;
         self
@@ -82,6 +94,11 @@ impl TrapspacesBuilder {
             }
         }
 
+        // Filtered variables must be fixed at the selected value
+        for (vid, b) in self.filters.iter() {
+            solver.add(&format!(":- not v{}.\n", 2 * vid + !*b as usize));
+        }
+
         // Remove the full state space from the solutions when computing elementary trapspaces
         if self.mode == SolverMode::MIN {
             let s = self
@@ -97,7 +114,6 @@ impl TrapspacesBuilder {
         results.set_halved();
 
         let patterns = results
-            .into_iter()
             .map(|r| r.into_pattern())
             .take(max.unwrap_or(10000))
             .collect_vec();
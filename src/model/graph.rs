@@ -0,0 +1,208 @@
+//! Extract the regulatory graph from the rules of a model
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use crate::func::expr::Expr;
+use crate::func::monotonicity::Effect;
+use crate::func::{Formula, Grouped, VariableNamer};
+use crate::model::QModel;
+use crate::variables::GroupedVariables;
+
+/// A signed interaction between two components.
+///
+/// The interaction is associated to the threshold of the source component
+/// denoting the Boolean variable used in the rules of the target.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Interaction {
+    pub source: usize,
+    pub threshold: usize,
+    pub target: usize,
+    pub effect: Effect,
+}
+
+/// Rules of an input component
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InputKind {
+    /// The component maintains its current level
+    FREE,
+    /// The component is fixed at a constant level
+    FIXED(usize),
+}
+
+impl QModel {
+    /// Identify all functional interactions based on the rules of the model.
+    ///
+    /// The effect of a regulator is combined over all thresholds of the target component.
+    /// Regulators which appear in a rule without affecting it are not included.
+    pub fn interaction_graph(&self) -> Vec<Interaction> {
+        let mut interactions = vec![];
+        for target in self.components() {
+            let rules = match self.rules.get(*target) {
+                None => continue,
+                Some(r) => r,
+            };
+
+            let mut effects: BTreeMap<usize, Effect> = BTreeMap::new();
+            for value in 1..=self.get_variables(*target).len() {
+                let formula = Formula::from(rules.raw_variable_formula(value));
+                for (vid, effect) in formula.regulator_effects() {
+                    let e = effects.entry(vid).or_insert(Effect::NONE);
+                    *e = e.merge(effect);
+                }
+            }
+
+            for (vid, effect) in effects {
+                if !effect.is_functional() {
+                    continue;
+                }
+                if let Some(var) = self.get_component_value(vid) {
+                    interactions.push(Interaction {
+                        source: var.component,
+                        threshold: var.value,
+                        target: *target,
+                        effect,
+                    });
+                }
+            }
+        }
+        interactions
+    }
+
+    /// Identify if a component is an input: its rules maintain its current level or fix it
+    /// at a constant level.
+    ///
+    /// The current level is compared on the states respecting the order of the thresholds.
+    pub fn input_kind(&self, cid: usize) -> Option<InputKind> {
+        let variables = self.get_variables(cid);
+        let conditions: Vec<Expr> = match self.rules.get(cid) {
+            None => return Some(InputKind::FIXED(0)),
+            Some(rules) => (1..=variables.len())
+                .map(|value| rules.raw_variable_formula(value))
+                .collect(),
+        };
+
+        if conditions.iter().all(|e| is_constant(e).is_some()) {
+            let level = conditions
+                .iter()
+                .filter(|e| is_constant(e) == Some(true))
+                .count();
+            return Some(InputKind::FIXED(level));
+        }
+
+        let ordered = variables.windows(2).fold(Expr::TRUE, |e, w| {
+            e.and(&Expr::ATOM(w[0]).or(&Expr::NATOM(w[1])))
+        });
+        let free = conditions.iter().zip(variables.iter()).all(|(e, vid)| {
+            let cur = Expr::ATOM(*vid);
+            let differ = e.and(&cur.not()).or(&cur.and(&e.not()));
+            ordered.and(&differ).prime_implicants().is_empty()
+        });
+        if free {
+            Some(InputKind::FREE)
+        } else {
+            None
+        }
+    }
+
+    /// List the components with a self-identity or a constant rule
+    pub fn inputs(&self) -> Vec<usize> {
+        self.components()
+            .filter(|cid| self.input_kind(**cid).is_some())
+            .copied()
+            .collect()
+    }
+
+    /// List the components which do not regulate any other component
+    pub fn outputs(&self) -> Vec<usize> {
+        let regulators: HashSet<usize> = self
+            .interaction_graph()
+            .iter()
+            .filter(|i| i.source != i.target)
+            .map(|i| i.source)
+            .collect();
+        self.components()
+            .filter(|cid| !regulators.contains(cid))
+            .copied()
+            .collect()
+    }
+}
+
+/// Value of a constant expression
+fn is_constant(e: &Expr) -> Option<bool> {
+    if e.prime_implicants().is_empty() {
+        Some(false)
+    } else if e.not().prime_implicants().is_empty() {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+impl Grouped for Interaction {
+    fn gfmt(&self, namer: &dyn VariableNamer, f: &mut fmt::Formatter) -> fmt::Result {
+        namer.format_name(f, self.source)?;
+        if self.threshold != 1 {
+            write!(f, ":{}", self.threshold)?;
+        }
+        write!(f, " -> ")?;
+        namer.format_name(f, self.target)?;
+        write!(f, " ({})", self.effect)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::monotonicity::Effect;
+    use crate::model::graph::InputKind;
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn interaction_graph() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        model.set_rule_from_str(a, 1, "!B | C:2").unwrap();
+        model.set_rule_from_str(b, 1, "A & C | A & !C").unwrap();
+        model.set_rule_from_str(c, 1, "A").unwrap();
+        model.set_rule_from_str(c, 2, "A & !B").unwrap();
+
+        let graph = model.interaction_graph();
+        let edges: Vec<(usize, usize, usize, Effect)> = graph
+            .iter()
+            .map(|i| (i.source, i.threshold, i.target, i.effect))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (b, 1, a, Effect::NEGATIVE),
+                (c, 2, a, Effect::POSITIVE),
+                (a, 1, b, Effect::POSITIVE),
+                (a, 1, c, Effect::POSITIVE),
+                (b, 1, c, Effect::NEGATIVE),
+            ]
+        );
+    }
+
+    #[test]
+    fn inputs_and_outputs() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        let d = model.ensure("D");
+        model.set_rule_from_str(a, 1, "A | A:2").unwrap();
+        model.set_rule_from_str(a, 2, "A:2").unwrap();
+        model.set_rule_from_str(b, 1, "true").unwrap();
+        model.set_rule_from_str(c, 1, "A & B | C").unwrap();
+
+        assert_eq!(model.input_kind(a), Some(InputKind::FREE));
+        assert_eq!(model.input_kind(b), Some(InputKind::FIXED(1)));
+        assert_eq!(model.input_kind(c), None);
+        assert_eq!(model.input_kind(d), Some(InputKind::FIXED(0)));
+        assert_eq!(model.inputs(), vec![a, b, d]);
+        assert_eq!(model.outputs(), vec![c, d]);
+    }
+}
@@ -0,0 +1,251 @@
+//! Record the modifications of a model to enable undo and redo.
+//!
+//! Each edit saves the state of the model before the modification. As all parts of a model are
+//! shared (copy-on-write) references, saving a state is cheap, but the first subsequent modification
+//! of each part will copy it: recording can be disabled for bulk operations.
+//!
+//! Edits can be nested: a modifier starting an edit pauses the recording until it is done, all
+//! modifications performed in the meantime are then undone together.
+
+use std::rc::Rc;
+
+use crate::model::layout::Layout;
+use crate::model::modifier::booleanize::Booleanization;
+use crate::model::modifier::reduction::Reduction;
+use crate::model::provenance::Provenance;
+use crate::model::rule::Rules;
+use crate::model::QModel;
+use crate::variables::ModelVariables;
+
+/// List of reversible edits applied to a model
+pub struct History {
+    recording: bool,
+    done: Vec<Edit>,
+    undone: Vec<Edit>,
+}
+
+/// A reversible modification, given by a description and the state of the model before or after it
+struct Edit {
+    description: String,
+    state: Snapshot,
+}
+
+/// Saved state of all parts of a model, including its lineage
+struct Snapshot {
+    variables: Rc<ModelVariables>,
+    rules: Rc<Rules>,
+    layout: Option<Rc<Layout>>,
+    reduction: Option<Rc<Reduction>>,
+    booleanization: Option<Rc<Booleanization>>,
+    provenance: Provenance,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            recording: true,
+            done: vec![],
+            undone: vec![],
+        }
+    }
+}
+
+impl History {
+    /// Descriptions of the applied edits, from the oldest to the most recent
+    pub fn done(&self) -> impl Iterator<Item = &str> {
+        self.done.iter().map(|e| e.description.as_str())
+    }
+
+    /// Descriptions of the undone edits, starting with the next one to redo
+    pub fn undone(&self) -> impl Iterator<Item = &str> {
+        self.undone.iter().rev().map(|e| e.description.as_str())
+    }
+}
+
+impl Snapshot {
+    fn save(model: &QModel) -> Self {
+        Snapshot {
+            variables: Rc::clone(&model.variables),
+            rules: Rc::clone(&model.rules),
+            layout: model.layout.clone(),
+            reduction: model.reduction.clone(),
+            booleanization: model.booleanization.clone(),
+            provenance: model.provenance.clone(),
+        }
+    }
+
+    fn is_current(&self, model: &QModel) -> bool {
+        Rc::ptr_eq(&self.variables, &model.variables)
+            && Rc::ptr_eq(&self.rules, &model.rules)
+            && same_ref(&self.layout, &model.layout)
+            && same_ref(&self.reduction, &model.reduction)
+            && same_ref(&self.booleanization, &model.booleanization)
+            && self.provenance.lineage().len() == model.provenance.lineage().len()
+    }
+
+    fn restore(self, model: &mut QModel) {
+        model.variables = self.variables;
+        model.rules = self.rules;
+        model.layout = self.layout;
+        model.reduction = self.reduction;
+        model.booleanization = self.booleanization;
+        model.provenance = self.provenance;
+    }
+}
+
+impl QModel {
+    /// Start a reversible edit.
+    ///
+    /// If recording is enabled, this saves the current state and pauses the recording:
+    /// the following modifications are part of this edit until the call to ``end_edit``.
+    /// Returns true if a new edit was started.
+    pub fn start_edit(&mut self, description: &str) -> bool {
+        if !self.history.recording {
+            return false;
+        }
+        let edit = Edit {
+            description: description.to_owned(),
+            state: Snapshot::save(self),
+        };
+        self.history.done.push(edit);
+        self.history.recording = false;
+        true
+    }
+
+    /// Complete an edit started by ``start_edit`` and resume the recording.
+    ///
+    /// Edits which did not modify the model are discarded.
+    pub fn end_edit(&mut self, started: bool) {
+        if !started {
+            return;
+        }
+        self.history.recording = true;
+        let unchanged = match self.history.done.last() {
+            None => false,
+            Some(e) => e.state.is_current(self),
+        };
+        if unchanged {
+            self.history.done.pop();
+        } else {
+            self.history.undone.clear();
+        }
+    }
+
+    /// Apply a modification as a single reversible edit
+    pub fn edit<T>(&mut self, description: &str, f: impl FnOnce(&mut QModel) -> T) -> T {
+        let started = self.start_edit(description);
+        let result = f(self);
+        self.end_edit(started);
+        result
+    }
+
+    /// Apply a fallible modification as a single reversible edit.
+    ///
+    /// If the modification fails, the model is restored to its previous state.
+    pub fn try_edit<T, E>(
+        &mut self,
+        description: &str,
+        f: impl FnOnce(&mut QModel) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let saved = Snapshot::save(self);
+        let started = self.start_edit(description);
+        let result = f(self);
+        if result.is_err() {
+            saved.restore(self);
+        }
+        self.end_edit(started);
+        result
+    }
+
+    /// Enable or disable the recording of edits, returns the previous setting
+    pub fn set_recording(&mut self, b: bool) -> bool {
+        std::mem::replace(&mut self.history.recording, b)
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Forget all recorded edits
+    pub fn clear_history(&mut self) {
+        self.history.done.clear();
+        self.history.undone.clear();
+    }
+
+    /// Revert the last edit, returns its description or None if there is nothing to undo
+    pub fn undo(&mut self) -> Option<String> {
+        let edit = self.history.done.pop()?;
+        let current = Snapshot::save(self);
+        edit.state.restore(self);
+        self.history.undone.push(Edit {
+            description: edit.description.clone(),
+            state: current,
+        });
+        Some(edit.description)
+    }
+
+    /// Apply again the last undone edit, returns its description or None if there is nothing to redo
+    pub fn redo(&mut self) -> Option<String> {
+        let edit = self.history.undone.pop()?;
+        let current = Snapshot::save(self);
+        edit.state.restore(self);
+        self.history.done.push(Edit {
+            description: edit.description.clone(),
+            state: current,
+        });
+        Some(edit.description)
+    }
+}
+
+fn same_ref<T>(a: &Option<Rc<T>>, b: &Option<Rc<T>>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::model::modifier::perturbation::Perturbator;
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn undo_redo() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        model.set_rule_from_str(a, 1, "B").unwrap();
+        model.set_rule_from_str(b, 1, "!A").unwrap();
+        model.clear_history();
+
+        // A perturbation is a single edit
+        let mut perturbator = Perturbator::new(&mut model);
+        perturbator.parse("A%0").unwrap();
+        perturbator.parse("B%1").unwrap();
+        perturbator.apply();
+        model.rename("A", "C").unwrap();
+        assert_eq!(model.history().done().count(), 2);
+
+        assert_eq!(model.undo().unwrap(), "rename A to C");
+        assert_eq!(model.get_name(a), "A");
+        assert_eq!(model.undo().unwrap(), "perturbation");
+        assert_eq!(model.get_var_rule(a), Expr::ATOM(b));
+        assert!(model.undo().is_none());
+
+        assert_eq!(model.redo().unwrap(), "perturbation");
+        assert_eq!(model.get_var_rule(a), Expr::FALSE);
+
+        // A new edit discards the undone ones
+        model.lock_component(b, 0).unwrap();
+        assert!(model.redo().is_none());
+        assert_eq!(model.history().done().count(), 2);
+
+        // Disable the recording
+        model.set_recording(false);
+        model.rename("A", "D").unwrap();
+        assert_eq!(model.history().done().count(), 2);
+    }
+}
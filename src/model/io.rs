@@ -5,14 +5,17 @@ use std::path::Path;
 use thiserror::Error;
 
 use crate::helper::error::{EmptyLomakResult, LomakResult};
+use crate::model::provenance::Provenance;
 use crate::model::{QModel, SharedModel};
+use crate::variables::{GroupedVariables, NamePolicy};
 
 mod bnet;
 mod boolsim;
-mod mnet;
+pub(crate) mod mnet;
 mod sbml;
 
 static FORMATS: [&str; 4] = ["bnet", "mnet", "bsim", "sbml"];
+static LABEL_PREFIX: &str = "# label ";
 
 /// A Format may provide import and export filters
 pub trait Format: TrySaving + TryParsing {
@@ -56,15 +59,46 @@ impl<T: ParsingFormat> TryParsing for T {
 /// Trait providing the import filter for Formats.
 pub trait ParsingFormat {
     fn parse_file(&self, filename: &str) -> LomakResult<SharedModel> {
+        self.parse_source(filename, None, NamePolicy::default())
+    }
+
+    /// Load a model from a file, and record its source and format in the provenance
+    fn parse_source(
+        &self,
+        filename: &str,
+        format: Option<&str>,
+        policy: NamePolicy,
+    ) -> LomakResult<SharedModel> {
         // Load the input file into a local string
         let mut unparsed_file = String::new();
         File::open(filename)?.read_to_string(&mut unparsed_file)?;
-        self.parse_str(&unparsed_file)
+        let provenance = Provenance::parsed(Some(filename), format, &unparsed_file);
+        self.parse_with_provenance(&unparsed_file, policy, provenance)
     }
 
     fn parse_str(&self, expression: &str) -> LomakResult<SharedModel> {
-        let mut model = QModel::default();
+        self.parse_str_with(expression, NamePolicy::default())
+    }
+
+    /// Parse a model using a specific normalisation of invalid names
+    fn parse_str_with(&self, expression: &str, policy: NamePolicy) -> LomakResult<SharedModel> {
+        let provenance = Provenance::parsed(None, None, expression);
+        self.parse_with_provenance(expression, policy, provenance)
+    }
+
+    /// Parse a model which starts a new history and lineage from the given origin
+    fn parse_with_provenance(
+        &self,
+        expression: &str,
+        policy: NamePolicy,
+        provenance: Provenance,
+    ) -> LomakResult<SharedModel> {
+        let mut model = QModel::with_provenance(provenance);
+        model.set_name_policy(policy);
+        model.set_recording(false);
         self.parse_into_model(&mut model, expression)?;
+        read_labels(&mut model, expression);
+        model.set_recording(true);
         Ok(SharedModel::with(model))
     }
 
@@ -84,10 +118,10 @@ pub trait SavingFormat {
 
 pub fn get_format(fmt: &str) -> Result<Box<dyn Format>, FormatError> {
     match fmt.to_lowercase().trim() {
-        "mnet" => Result::Ok(Box::new(mnet::MNETFormat::default())),
-        "bnet" => Result::Ok(Box::new(bnet::BNETFormat::default())),
-        "bsim" => Result::Ok(Box::new(boolsim::BoolSimFormat::default())),
-        "sbml" => Result::Ok(Box::new(sbml::SBMLFormat::default())),
+        "mnet" => Result::Ok(Box::new(mnet::MNETFormat)),
+        "bnet" => Result::Ok(Box::new(bnet::BNETFormat)),
+        "bsim" => Result::Ok(Box::new(boolsim::BoolSimFormat)),
+        "sbml" => Result::Ok(Box::new(sbml::SBMLFormat)),
         _ => Err(FormatError::NotFound(fmt.to_owned())),
     }
 }
@@ -101,13 +135,62 @@ fn guess_format(filename: &str) -> Result<Box<dyn Format>, FormatError> {
 }
 
 pub fn load_model(filename: &str, fmt: Option<&str>) -> LomakResult<SharedModel> {
+    load_model_with(filename, fmt, NamePolicy::default())
+}
+
+/// Load a model using a specific normalisation of invalid names
+pub fn load_model_with(
+    filename: &str,
+    fmt: Option<&str>,
+    policy: NamePolicy,
+) -> LomakResult<SharedModel> {
     let f = match fmt {
         None => guess_format(filename),
         Some(s) => get_format(s),
     }?;
 
+    let format = match fmt {
+        Some(s) => Some(s.trim().to_lowercase()),
+        None => Path::new(filename)
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(str::to_lowercase),
+    };
+
     let parser = f.as_parser()?;
-    parser.parse_file(filename)
+    parser.parse_source(filename, format.as_deref(), policy)
+}
+
+/// Write the provenance of a model as comment lines using the given prefix
+pub fn write_provenance(model: &QModel, prefix: &str, out: &mut dyn Write) -> EmptyLomakResult {
+    for line in model.provenance().to_string().lines() {
+        writeln!(out, "{}{}", prefix, line)?;
+    }
+    Ok(())
+}
+
+/// Write the original names of labelled components as comment lines
+pub fn write_labels(model: &QModel, out: &mut dyn Write) -> EmptyLomakResult {
+    for (cid, label) in model.labels() {
+        writeln!(out, "{}{}: {}", LABEL_PREFIX, model.get_name(cid), label)?;
+    }
+    Ok(())
+}
+
+/// Restore the original names saved by write_labels
+fn read_labels(model: &mut QModel, expression: &str) {
+    for line in expression.lines() {
+        let (name, label) = match line
+            .strip_prefix(LABEL_PREFIX)
+            .and_then(|l| l.split_once(": "))
+        {
+            None => continue,
+            Some(l) => l,
+        };
+        if let Some(cid) = model.get_handle(name.trim()) {
+            model.set_label(cid, label);
+        }
+    }
 }
 
 pub fn save_model(model: &QModel, filename: &str, fmt: Option<&str>) -> EmptyLomakResult {
@@ -24,7 +24,7 @@ grp   = _{ neg | bt | bf | lit | "(" ~ expr ~ ")" }
 bt    =  { ^"true" | "1" }
 bf    =  { ^"false" | "0" }
 lit   = @{ uid }
-value =  { ASCII_DIGIT }
+value = @{ ASCII_NONZERO_DIGIT ~ ASCII_DIGIT* }
 uid   = @{ (ASCII_ALPHA | "_") ~ (ASCII_ALPHANUMERIC | "_")* }
 
 WHITESPACE = _{ " " | "\t" }
@@ -44,14 +44,14 @@ impl Format for BNETFormat {
 
 impl BNETFormat {
     fn load_expr(&self, model: &mut QModel, expr: Pair<Rule>) -> Expr {
-        let rule = expr.as_rule();
-        match rule {
+        let rtype = expr.as_rule();
+        match rtype {
             Rule::bt => Expr::TRUE,
             Rule::bf => Expr::FALSE,
             Rule::lit => Expr::ATOM(model.ensure(expr.as_str())),
             _ => {
                 let mut content = expr.into_inner().map(|e| self.load_expr(model, e));
-                match rule {
+                match rtype {
                     Rule::conj => Operator::AND.join(&mut content),
                     Rule::disj => Operator::OR.join(&mut content),
                     Rule::neg => content.next().unwrap().not(),
@@ -96,6 +96,8 @@ impl io::ParsingFormat for BNETFormat {
 
 impl io::SavingFormat for BNETFormat {
     fn write_rules(&self, model: &QModel, out: &mut dyn Write) -> EmptyLomakResult {
+        io::write_provenance(model, "# ", out)?;
+        io::write_labels(model, out)?;
         for vid in model.variables() {
             let func: Expr = model.get_var_rule(*vid);
             write!(out, "{}, ", model.get_name(*vid))?;
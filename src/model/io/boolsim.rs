@@ -20,7 +20,7 @@ expr  =  { ( term ~ ( "&" ~ term )* )? }
 term  = _{ neg | lit }
 neg   =  { ( "^" | "!" | "~") ~ lit }
 lit   = @{ uid }
-value =  { ASCII_DIGIT }
+value = @{ ASCII_NONZERO_DIGIT ~ ASCII_DIGIT* }
 uid   = @{ (ASCII_ALPHA | "_") ~ (ASCII_ALPHANUMERIC | "_")* }
 
 WHITESPACE = _{ " " | "\t" }
@@ -40,12 +40,12 @@ impl Format for BoolSimFormat {
 
 impl BoolSimFormat {
     fn load_expr(&self, model: &mut QModel, expr: Pair<Rule>) -> Expr {
-        let rule = expr.as_rule();
-        match rule {
+        let rtype = expr.as_rule();
+        match rtype {
             Rule::lit => Expr::ATOM(model.ensure(expr.as_str())),
             _ => {
                 let mut content = expr.into_inner().map(|e| self.load_expr(model, e));
-                match rule {
+                match rtype {
                     Rule::expr => Operator::AND.join(&mut content),
                     Rule::neg => content.next().unwrap().not(),
                     // Other rules are outside of scope or hidden
@@ -1,10 +1,9 @@
 use std::io::Write;
 
-use pest::iterators::*;
 use pest::Parser;
 
-use crate::func::expr::{Expr, NamedExpr, Operator};
-use crate::func::Formula;
+use crate::func::expr::NamedExpr;
+use crate::func::{str2expr, Formula};
 use crate::helper::error::EmptyLomakResult;
 use crate::model::io::Format;
 use crate::model::QModel;
@@ -20,11 +19,13 @@ disj  =  { conj ~ ( "|"  ~ conj )* }
 conj  =  { term ~ ( "&" ~ term )* }
 term  = _{ neg | grp }
 neg   =  { ("!" | "~") ~ grp }
-grp   = _{ neg | bt | bf | lit | "(" ~ expr ~ ")" }
+grp   = _{ neg | bt | bf | cmp | lit | "(" ~ expr ~ ")" }
 bt    =  { ^"true" | "1" }
 bf    =  { ^"false" | "0" }
+cmp   =  { uid ~ oper ~ value }
+oper  =  { ">=" | "<=" | "!=" | "==" | "=" | ">" | "<" }
 lit   = ${ uid ~ (":" ~ value)?  }
-value =  { ASCII_DIGIT }
+value = @{ ASCII_DIGIT+ }
 uid   = @{ (ASCII_ALPHA | "_") ~ (ASCII_ALPHANUMERIC | "_")* }
 
 WHITESPACE = _{ " " | "\t" }
@@ -42,37 +43,6 @@ impl Format for MNETFormat {
     }
 }
 
-impl MNETFormat {
-    fn load_expr(model: &mut QModel, expr: Pair<Rule>) -> Expr {
-        let rule = expr.as_rule();
-        match rule {
-            Rule::bt => Expr::TRUE,
-            Rule::bf => Expr::FALSE,
-            Rule::lit => Expr::ATOM(Self::load_lit(model, expr)),
-            _ => {
-                let mut content = expr.into_inner().map(|e| Self::load_expr(model, e));
-                match rule {
-                    Rule::conj => Operator::AND.join(&mut content),
-                    Rule::disj => Operator::OR.join(&mut content),
-                    Rule::neg => content.next().unwrap().not(),
-                    Rule::expr => content.next().unwrap(),
-                    // Other rules are outside of scope or hidden
-                    _ => panic!("Parsing tokens should not get there"),
-                }
-            }
-        }
-    }
-
-    fn load_lit(model: &mut QModel, expr: Pair<Rule>) -> usize {
-        let mut expr = expr.into_inner();
-        let cid = model.ensure(expr.next().unwrap().as_str());
-        if let Some(e) = expr.next() {
-            return model.ensure_threshold(cid, e.as_str().parse().unwrap());
-        }
-        cid
-    }
-}
-
 impl io::ParsingFormat for MNETFormat {
     fn parse_into_model(&self, model: &mut QModel, expression: &str) -> EmptyLomakResult {
         let mut ptree = MNETParser::parse(Rule::file, expression)?;
@@ -85,7 +55,7 @@ impl io::ParsingFormat for MNETFormat {
                 Rule::rule => {
                     let mut inner = record.into_inner();
                     let target = inner.next().unwrap();
-                    let var = Self::load_lit(model, target);
+                    let var = str2expr::load_variable(model, target)?;
                     expressions.push((var, inner.next().unwrap()));
                 }
                 Rule::EOI => (),
@@ -95,7 +65,7 @@ impl io::ParsingFormat for MNETFormat {
 
         // Parse all expressions
         for (vid, e) in expressions {
-            let expr = Self::load_expr(model, e);
+            let expr = str2expr::load_expr(model, e)?;
             model.push_var_rule(vid, Formula::from(expr));
         }
 
@@ -105,6 +75,8 @@ impl io::ParsingFormat for MNETFormat {
 
 impl io::SavingFormat for MNETFormat {
     fn write_rules(&self, model: &QModel, out: &mut dyn Write) -> EmptyLomakResult {
+        io::write_provenance(model, "# ", out)?;
+        io::write_labels(model, out)?;
         for cid in model.components() {
             let rule = model.rules.get(*cid).unwrap();
             let name = model.get_name(*cid);
@@ -5,6 +5,7 @@ use crate::model::{io, GroupedVariables, QModel};
 use std::io::Write;
 
 use crate::helper::error::{CanFail, EmptyLomakResult, GenericError, LomakError, ParseError};
+use crate::model::graph::InputKind;
 use crate::model::io::Format;
 use crate::model::layout::NodeLayoutInfo;
 use once_cell::sync::Lazy;
@@ -58,6 +59,17 @@ impl io::SavingFormat for SBMLFormat {
         w.start_element("model");
         w.write_attribute("id", "model_id");
 
+        // Trace the origin of the model in the notes
+        w.start_element("notes");
+        w.start_element("body");
+        w.write_attribute("xmlns", "http://www.w3.org/1999/xhtml");
+        w.start_element("pre");
+        w.write_attribute("class", "lomak-provenance");
+        w.write_text(&model.provenance().to_json());
+        w.end_element();
+        w.end_element();
+        w.end_element();
+
         // The single compartment
         w.start_element("listOfCompartments");
         w.start_element("compartments");
@@ -105,9 +117,19 @@ impl io::SavingFormat for SBMLFormat {
             let max = model.get_variables(*uid).len();
             w.start_element("qual:qualitativeSpecies");
             w.write_attribute("qual:id", model.get_name(*uid));
+            if let Some(label) = model.get_label(*uid) {
+                w.write_attribute("qual:name", label);
+            }
             w.write_attribute("qual:compartment", "comp1");
-            // TODO: detect and annotate inputs nodes?
-            w.write_attribute("qual:constant", "false");
+            // Inputs are constant species, fixed inputs also define their level
+            match model.input_kind(*uid) {
+                None => w.write_attribute("qual:constant", "false"),
+                Some(InputKind::FREE) => w.write_attribute("qual:constant", "true"),
+                Some(InputKind::FIXED(level)) => {
+                    w.write_attribute("qual:constant", "true");
+                    w.write_attribute("qual:initialLevel", &level);
+                }
+            }
             w.write_attribute("qual:maxLevel", &max);
             w.end_element();
         }
@@ -118,7 +140,11 @@ impl io::SavingFormat for SBMLFormat {
         w.write_attribute("xmlns:qual", qual_ns);
 
         for uid in model.components() {
-            let rule = model.rules.get(*uid).unwrap();
+            // Constant species can not be the output of a transition
+            let rule = match model.rules.get(*uid) {
+                Some(rule) if model.input_kind(*uid).is_none() => rule,
+                _ => continue,
+            };
             let name = model.get_name(*uid);
 
             w.start_element("qual:transition");
@@ -164,19 +190,23 @@ impl io::SavingFormat for SBMLFormat {
 }
 
 fn write_atom(model: &QModel, vid: usize, w: &mut XmlWriter) {
-    // TODO: Handle multivalued
+    // A variable denotes a threshold of its component
+    let (cid, value) = match model.get_component_value(vid) {
+        None => (vid, 1),
+        Some(var) => (var.component, var.value),
+    };
     w.start_element("apply");
 
     w.start_element("geq");
     w.end_element();
 
     w.start_element("ci");
-    w.write_text(model.get_name(vid));
+    w.write_text(model.get_name(cid));
     w.end_element();
 
     w.start_element("cn");
     w.write_attribute("type", "integer");
-    w.write_text("1");
+    w.write_text(&value.to_string());
     w.end_element();
 
     w.end_element();
@@ -247,7 +277,7 @@ impl io::ParsingFormat for SBMLFormat {
     }
 }
 
-pub fn load_xml(expression: &str) -> Result<Document, ParseError> {
+pub fn load_xml(expression: &str) -> Result<Document<'_>, ParseError> {
     let parsed = roxmltree::Document::parse(expression);
 
     match parsed {
@@ -296,7 +326,7 @@ impl SBMLParser {
             .children()
             .find(|n| n.has_tag_name((ns_qual, "listOfQualitativeSpecies")))
         {
-            SBMLParser::parse_species(ns_qual, model, species.children());
+            SBMLParser::parse_species(ns_qual, model, species.children())?;
         }
 
         // Add transitions
@@ -330,7 +360,7 @@ impl SBMLParser {
         Ok(())
     }
 
-    fn parse_species(ns: &str, model: &mut QModel, species: Children) {
+    fn parse_species(ns: &str, model: &mut QModel, species: Children) -> CanFail<ParseError> {
         for n_qs in species {
             if !n_qs.has_tag_name("qualitativeSpecies") {
                 continue;
@@ -338,26 +368,66 @@ impl SBMLParser {
 
             // Create the main variable for this species
             let sid = n_qs.attribute((ns, "id")).unwrap();
-            let uid = model.ensure(sid);
+            let uid = model.try_ensure(sid)?;
+
+            // Keep the name as label if it differs from the identifier
+            if let Some(name) = n_qs.attribute((ns, "name")) {
+                if name != model.get_name(uid) {
+                    model.set_label(uid, name);
+                }
+            }
 
             // Retrieve the max level and create associated variables if needed
-            if let Some(Ok(m)) = n_qs.attribute((ns, "maxLevel")).map(|v| v.parse()) {
-                model.ensure_threshold(uid, m);
+            let max_level = match n_qs.attribute((ns, "maxLevel")).map(|v| v.parse()) {
+                Some(Ok(m)) => Some(m),
+                _ => None,
+            };
+            if let Some(m) = max_level {
+                if m > 0 {
+                    model.ensure_threshold(uid, m)?;
+                }
             }
 
-            // Add the implicit self-loop on constant species
+            // Constant species keep their initial level if defined, or get an implicit self-loop
             if n_qs
                 .attribute((ns, "constant"))
                 .unwrap_or("false")
                 .parse()
                 .unwrap_or(false)
             {
-                let variables: Vec<usize> = model.get_variables(uid).iter().copied().collect();
-                for curid in variables {
-                    model.push_var_rule(curid, Formula::from(Expr::ATOM(curid)));
+                match n_qs
+                    .attribute((ns, "initialLevel"))
+                    .map(|v| v.parse::<usize>())
+                {
+                    Some(Err(_)) => {
+                        let value = n_qs.attribute((ns, "initialLevel")).unwrap();
+                        let e = format!("Invalid initial level for {}: {}", sid, value);
+                        return Err(GenericError::new(e).into());
+                    }
+                    Some(Ok(level)) if max_level.is_some_and(|m| level > m) => {
+                        let e = format!(
+                            "The initial level of {} ({}) exceeds its max level ({})",
+                            sid,
+                            level,
+                            max_level.unwrap()
+                        );
+                        return Err(GenericError::new(e).into());
+                    }
+                    Some(Ok(0)) => (),
+                    Some(Ok(level)) => {
+                        model.ensure_threshold(uid, level)?;
+                        model.push_cpt_rule(uid, level, Formula::from(Expr::TRUE));
+                    }
+                    None => {
+                        let variables: Vec<usize> = model.get_variables(uid).to_vec();
+                        for curid in variables {
+                            model.push_var_rule(curid, Formula::from(Expr::ATOM(curid)));
+                        }
+                    }
                 }
             }
         }
+        Ok(())
     }
 
     fn parse_transitions(
@@ -422,7 +492,7 @@ impl SBMLParser {
             for o in outputs {
                 let target = match o
                     .attribute((ns, "qualitativeSpecies"))
-                    .map(|t| model.get_handle(t))
+                    .map(|t| model.find_identifier(t))
                 {
                     Some(Some(t)) => t,
                     _ => {
@@ -446,7 +516,7 @@ impl SBMLParser {
             return Err(GenericError::new("Missing content in mathml?".to_owned()).into());
         }
 
-        let name = children.get(0).unwrap().tag_name().name();
+        let name = children.first().unwrap().tag_name().name();
         let params = &children[1..];
         match name {
             "eq" => SBMLParser::parse_comparison(model, Comparator::EQ, params),
@@ -460,15 +530,13 @@ impl SBMLParser {
             "not" => SBMLParser::parse_not(model, params),
             "true" => Ok(Expr::TRUE),
             "false" => Ok(Expr::FALSE),
-            _ => {
-                return Err(GenericError::new(format!(
-                    "Unsupported mathml tag: {} ({:?})",
-                    name,
-                    math.document()
-                        .text_pos_at(children.get(0).unwrap().range().start)
-                ))
-                .into())
-            }
+            _ => Err(GenericError::new(format!(
+                "Unsupported mathml tag: {} ({:?})",
+                name,
+                math.document()
+                    .text_pos_at(children.first().unwrap().range().start)
+            ))
+            .into()),
         }
     }
 
@@ -494,7 +562,7 @@ impl SBMLParser {
             }
         }
 
-        let var = match variable.map(|v| model.get_handle(v.trim())) {
+        let var = match variable.map(|v| model.find_identifier(v.trim())) {
             Some(Some(u)) => u,
             _ => {
                 return Err(
@@ -559,7 +627,7 @@ impl SBMLParser {
             {
                 let uid = match glyph
                     .attribute((ns, "reference"))
-                    .map(|r| model.get_handle(r))
+                    .map(|r| model.find_identifier(r))
                 {
                     Some(Some(uid)) => uid,
                     _ => continue,
@@ -585,3 +653,117 @@ impl SBMLParser {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::model::graph::InputKind;
+    use crate::model::io::sbml::SBMLFormat;
+    use crate::model::io::{ParsingFormat, SavingFormat};
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    /// Wrap a list of qualitative species in a minimal SBML document
+    fn sbml_species(species: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbml xmlns="http://www.sbml.org/sbml/level3/version1/core" level="3" version="1"
+      xmlns:qual="http://www.sbml.org/sbml/level3/version1/qual/version1" qual:required="true">
+  <model id="test">
+    <qual:listOfQualitativeSpecies>{}</qual:listOfQualitativeSpecies>
+  </model>
+</sbml>"#,
+            species
+        )
+    }
+
+    #[test]
+    fn invalid_levels() {
+        let species = r#"<qual:qualitativeSpecies qual:id="A" qual:maxLevel="2"/>"#;
+        let mut model = QModel::default();
+        SBMLFormat
+            .parse_into_model(&mut model, &sbml_species(species))
+            .unwrap();
+        assert_eq!(model.get_variables(model.get_handle("A").unwrap()).len(), 2);
+
+        let species = r#"<qual:qualitativeSpecies qual:id="A" qual:maxLevel="100000000"/>"#;
+        let mut model = QModel::default();
+        assert!(SBMLFormat
+            .parse_into_model(&mut model, &sbml_species(species))
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_initial_levels() {
+        let species = r#"<qual:qualitativeSpecies qual:id="A" qual:maxLevel="2" qual:constant="true" qual:initialLevel="2"/>"#;
+        let mut model = QModel::default();
+        SBMLFormat
+            .parse_into_model(&mut model, &sbml_species(species))
+            .unwrap();
+        let a = model.get_handle("A").unwrap();
+        assert_eq!(model.input_kind(a), Some(InputKind::FIXED(2)));
+
+        for level in &["3", "x", "-1"] {
+            let species = format!(
+                r#"<qual:qualitativeSpecies qual:id="A" qual:maxLevel="2" qual:constant="true" qual:initialLevel="{}"/>"#,
+                level
+            );
+            let mut model = QModel::default();
+            assert!(SBMLFormat
+                .parse_into_model(&mut model, &sbml_species(&species))
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn save_inputs() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        model.ensure("B");
+        let c = model.ensure("C");
+        let d = model.ensure("D");
+        model.set_rule_from_str(a, 1, "A").unwrap();
+        model.ensure_threshold(c, 2).unwrap();
+        model.set_rule_from_str(c, 2, "true").unwrap();
+        model.set_rule_from_str(d, 1, "A & !B | C:2").unwrap();
+
+        let mut out: Vec<u8> = vec![];
+        SBMLFormat.write_rules(&model, &mut out).unwrap();
+        let mut loaded = QModel::default();
+        SBMLFormat
+            .parse_into_model(&mut loaded, &String::from_utf8(out).unwrap())
+            .unwrap();
+
+        let expected = [
+            ("A", Some(InputKind::FREE)),
+            ("B", Some(InputKind::FIXED(0))),
+            ("C", Some(InputKind::FIXED(2))),
+            ("D", None),
+        ];
+        for (name, kind) in expected.iter() {
+            let cid = loaded.get_handle(name).unwrap();
+            assert_eq!(loaded.input_kind(cid), *kind, "input kind of {}", name);
+        }
+    }
+
+    #[test]
+    fn labels_and_identifiers() {
+        let species = r#"<qual:qualitativeSpecies qual:id="Akt_p" qual:name="Akt"/>
+            <qual:qualitativeSpecies qual:id="Akt"/>"#;
+        let mut model = QModel::default();
+        SBMLFormat
+            .parse_into_model(&mut model, &sbml_species(species))
+            .unwrap();
+        assert_eq!(model.components().len(), 2);
+        let akt = model.get_handle("Akt").unwrap();
+        assert_eq!(model.get_name(akt), "Akt");
+
+        // Labels are used to find components only if they do not match an identifier
+        let akt_p = model.get_handle("Akt_p").unwrap();
+        model.set_label(akt_p, "pAkt");
+        assert_eq!(model.get_handle("pAkt"), Some(akt_p));
+        model.set_label(akt_p, "Akt-P");
+        assert_eq!(model.get_handle("pAkt"), None);
+        assert_eq!(model.get_handle("Akt-P"), Some(akt_p));
+        assert_eq!(model.find_identifier("Akt-P"), None);
+    }
+}
@@ -8,7 +8,7 @@ pub struct Layout {
     version: Version,
 }
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct NodeLayoutInfo {
     pub x: usize,
     pub y: usize,
@@ -23,6 +23,9 @@ impl Layout {
     pub fn get_bounding_box(&self, uid: usize) -> Option<&NodeLayoutInfo> {
         self.data.get(&uid)
     }
+    pub fn remove(&mut self, uid: usize) -> Option<NodeLayoutInfo> {
+        self.data.remove(&uid)
+    }
 }
 
 impl Versionned for Layout {
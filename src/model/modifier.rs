@@ -1,2 +1,8 @@
+pub mod booleanize;
 pub mod buffer;
+pub mod extract;
+pub mod merge;
+pub mod multivalued;
 pub mod perturbation;
+pub mod reduction;
+pub mod rename;
@@ -0,0 +1,340 @@
+//! Convert a multivalued model into a standalone Boolean model.
+//!
+//! Each threshold variable of a multivalued component becomes a Boolean component named after
+//! the threshold (``A_b1``, ``A_b2``...), Boolean components keep their name. The encoding
+//! selects the rule of each threshold variable:
+//! * Van Ham: a threshold can only change if it does not break the order with the adjacent
+//!   thresholds, the component moves one level at a time as in the multivalued model.
+//! * Ordered: each threshold follows the condition required to reach it. These conditions are
+//!   nested, so the targets respect the order, but components can jump over several levels.
+//!
+//! In both cases the fixed points of the Boolean model correspond to those of the original model.
+//! The resulting model records the mapping to restore the multivalued variables, as long as its
+//! components and rules are not modified.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::func::expr::{AtomReplacer, Expr};
+use crate::func::pattern::Pattern;
+use crate::helper::error::GenericError;
+use crate::model::actions::fixpoints::FixedPoints;
+use crate::model::modifier::reduction::Reduction;
+use crate::model::rule::{ComponentRules, Rules};
+use crate::model::QModel;
+use crate::variables::{GroupedVariables, ModelVariables};
+
+/// Boolean rules associated to the threshold variables
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BooleanEncoding {
+    /// Unitary changes of levels, enforcing the order of thresholds in the rules
+    VANHAM,
+    /// Each threshold follows its own condition
+    ORDERED,
+}
+
+/// Record of the threshold variables of the original model, to map results back
+#[derive(Clone)]
+pub struct Booleanization {
+    original: Rc<ModelVariables>,
+    reduction: Option<Rc<Reduction>>,
+    encoding: BooleanEncoding,
+    /// Original threshold variable and new Boolean component, in the order of components
+    mapping: Vec<(usize, usize)>,
+    components: HashMap<usize, usize>,
+    variables: HashMap<usize, usize>,
+    /// The Boolean model matching this mapping, later modifications invalidate it
+    boolean_variables: Rc<ModelVariables>,
+    boolean_rules: Rc<Rules>,
+}
+
+/// Build a Boolean version of a model
+pub struct Booleanizer<'a> {
+    model: &'a QModel,
+    encoding: BooleanEncoding,
+}
+
+/// Replace the original variables with the new Boolean components
+struct Translation<'a> {
+    mapping: &'a HashMap<usize, usize>,
+}
+
+impl AtomReplacer for Translation<'_> {
+    fn replace(&mut self, var: usize, value: bool) -> Option<Expr> {
+        self.mapping.get(&var).map(|cid| {
+            if value {
+                Expr::ATOM(*cid)
+            } else {
+                Expr::NATOM(*cid)
+            }
+        })
+    }
+}
+
+impl FromStr for BooleanEncoding {
+    type Err = GenericError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "vanham" | "van-ham" => Ok(BooleanEncoding::VANHAM),
+            "ordered" => Ok(BooleanEncoding::ORDERED),
+            _ => Err(GenericError::new(format!(
+                "Unknown Boolean encoding: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for BooleanEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BooleanEncoding::VANHAM => write!(f, "vanham"),
+            BooleanEncoding::ORDERED => write!(f, "ordered"),
+        }
+    }
+}
+
+impl<'a> Booleanizer<'a> {
+    pub fn new(model: &'a QModel) -> Self {
+        Booleanizer {
+            model,
+            encoding: BooleanEncoding::VANHAM,
+        }
+    }
+
+    pub fn set_encoding(&mut self, encoding: BooleanEncoding) -> &mut Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Create the Boolean model, which records the mapping to the original variables
+    pub fn apply(&self) -> QModel {
+        let model = self.model;
+        let mut provenance = model.provenance().derive();
+        provenance.push("booleanize", vec![self.encoding.to_string()]);
+        let mut result = QModel::with_provenance(provenance);
+        result.set_recording(false);
+
+        // Create a Boolean component for each threshold variable
+        let mut mapping = vec![];
+        for cid in model.components() {
+            let variables = model.get_variables(*cid);
+            let name = model.get_name(*cid);
+            for (idx, vid) in variables.iter().enumerate() {
+                let new_name = if variables.len() == 1 {
+                    name.to_owned()
+                } else {
+                    format!("{}_b{}", name, idx + 1)
+                };
+                let new_name = result.find_free_name(&new_name).unwrap_or(new_name);
+                let new_cid = result.ensure(&new_name);
+                if idx == 0 {
+                    if let Some(label) = model.get_label(*cid) {
+                        result.set_label(new_cid, label);
+                    }
+                    if let Some(bb) = model.get_bounding_box(*cid) {
+                        result.set_bounding_box(new_cid, *bb);
+                    }
+                }
+                mapping.push((*vid, new_cid));
+            }
+        }
+
+        let components: HashMap<usize, usize> = mapping.iter().copied().collect();
+        let mut translation = Translation {
+            mapping: &components,
+        };
+        for (vid, new_cid) in mapping.iter() {
+            let e = match self.encoding {
+                BooleanEncoding::VANHAM => model.get_var_rule(*vid),
+                BooleanEncoding::ORDERED => {
+                    let var = model.get_component_value(*vid).unwrap();
+                    model
+                        .rules
+                        .get(var.component)
+                        .map(|r| r.raw_variable_formula(var.value))
+                        .unwrap_or(Expr::FALSE)
+                }
+            };
+            let e = e.replace_variables(&mut translation).unwrap_or(e);
+            let rules = ComponentRules::from_thresholds(vec![e]);
+            Rc::make_mut(&mut result.rules).replace(*new_cid, rules);
+        }
+
+        let variables = mapping.iter().map(|(vid, cid)| (*cid, *vid)).collect();
+        result.booleanization = Some(Rc::new(Booleanization {
+            original: model.frozen_variables(),
+            reduction: model.reduction(),
+            encoding: self.encoding,
+            mapping,
+            components,
+            variables,
+            boolean_variables: Rc::clone(&result.variables),
+            boolean_rules: Rc::clone(&result.rules),
+        }));
+        result.set_recording(true);
+        result
+    }
+}
+
+impl Booleanization {
+    /// The variables of the multivalued model
+    pub fn original_variables(&self) -> Rc<ModelVariables> {
+        Rc::clone(&self.original)
+    }
+
+    pub fn encoding(&self) -> BooleanEncoding {
+        self.encoding
+    }
+
+    /// Pairs of original threshold variables and Boolean components
+    pub fn mapping(&self) -> &[(usize, usize)] {
+        &self.mapping
+    }
+
+    /// The Boolean component associated to a threshold variable of the original model
+    pub fn boolean_component(&self, vid: usize) -> Option<usize> {
+        self.components.get(&vid).copied()
+    }
+
+    /// The threshold variable of the original model associated to a Boolean component
+    pub fn original_variable(&self, cid: usize) -> Option<usize> {
+        self.variables.get(&cid).copied()
+    }
+
+    /// Check if a model is the unmodified Boolean model associated to this mapping
+    pub fn is_current(&self, model: &QModel) -> bool {
+        Rc::ptr_eq(&self.boolean_variables, &model.variables)
+            && Rc::ptr_eq(&self.boolean_rules, &model.rules)
+    }
+
+    /// Map a pattern of the Boolean model to the threshold variables of the original model.
+    ///
+    /// Returns None if the pattern activates a threshold while a lower one is inactive,
+    /// which does not correspond to any level of the original component.
+    pub fn expand(&self, p: &Pattern) -> Option<Pattern> {
+        let mut result = Pattern::new();
+        for (vid, cid) in self.mapping.iter() {
+            if p.is_fixed_at(*cid, true) {
+                result.set(*vid, true);
+            } else if p.is_fixed_at(*cid, false) {
+                result.set(*vid, false);
+            }
+        }
+
+        for cid in self.original.components() {
+            let variables = self.original.get_variables(*cid);
+            let broken = variables
+                .windows(2)
+                .any(|w| result.is_fixed_at(w[0], false) && result.is_fixed_at(w[1], true));
+            if broken {
+                return None;
+            }
+        }
+        Some(result)
+    }
+
+    /// Map fixed points of the Boolean model back to the original model.
+    ///
+    /// If the original model was reduced, the fixed points are also expanded to the full model.
+    pub fn expand_fixed(&self, fixed: &FixedPoints) -> FixedPoints {
+        let patterns = fixed
+            .patterns()
+            .iter()
+            .filter_map(|p| self.expand(p))
+            .collect();
+        let result = FixedPoints::new(self.original_variables(), patterns);
+        match &self.reduction {
+            None => result,
+            Some(reduction) => reduction.expand_fixed(&result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::func::pattern::Pattern;
+    use crate::model::actions::fixpoints::FixedPoints;
+    use crate::model::modifier::booleanize::{BooleanEncoding, Booleanizer};
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn booleanize_model() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        model.set_rule_from_str(a, 1, "B").unwrap();
+        model.set_rule_from_str(a, 2, "!B").unwrap();
+        model.set_rule_from_str(b, 1, "A:2").unwrap();
+        let a2 = model.get_variable(a, 2).unwrap();
+
+        let boolean = Booleanizer::new(&model).apply();
+        let names: Vec<&str> = boolean.components().map(|c| boolean.get_name(*c)).collect();
+        assert_eq!(names, vec!["A_b1", "A_b2", "B"]);
+        assert!(boolean
+            .components()
+            .all(|c| boolean.get_variables(*c).len() == 1));
+        let mapping = boolean.booleanization().unwrap();
+        let b1 = mapping.boolean_component(a).unwrap();
+        let b2 = mapping.boolean_component(a2).unwrap();
+        let bb = mapping.boolean_component(b).unwrap();
+        assert_eq!(mapping.original_variable(b2), Some(a2));
+
+        // The second threshold can only be reached from the first one
+        let expected = Expr::ATOM(b1).and(&Expr::NATOM(bb));
+        assert!(boolean.get_var_rule(b2).is_equivalent(&expected));
+        assert_eq!(boolean.provenance().lineage()[0].arguments, vec!["vanham"]);
+
+        let ordered = Booleanizer::new(&model)
+            .set_encoding(BooleanEncoding::ORDERED)
+            .apply();
+        assert!(ordered.get_var_rule(b2).is_equivalent(&Expr::NATOM(bb)));
+
+        // Map Boolean states back to the thresholds
+        let mut p = Pattern::new();
+        p.set(b1, true);
+        p.set(b2, false);
+        p.set(bb, true);
+        let expanded = mapping.expand(&p).unwrap();
+        assert!(expanded.is_fixed_at(a, true) && expanded.is_fixed_at(a2, false));
+        assert!(expanded.is_fixed_at(b, true));
+        p.set(b1, false);
+        p.set(b2, true);
+        assert!(mapping.expand(&p).is_none());
+
+        let fixed = FixedPoints::new(boolean.frozen_variables(), vec![p, Pattern::new()]);
+        assert_eq!(mapping.expand_fixed(&fixed).patterns().len(), 1);
+    }
+
+    #[test]
+    fn booleanize_history() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        model.set_rule_from_str(a, 2, "A:2").unwrap();
+
+        // Replacing the model is a reversible edit
+        let boolean = Booleanizer::new(&model).apply();
+        model.replace_with("booleanize", boolean);
+        assert!(model.booleanization().is_some());
+        assert_eq!(model.components().len(), 2);
+        assert_eq!(model.history().done().last(), Some("booleanize"));
+        model.undo();
+        assert!(model.booleanization().is_none());
+        assert_eq!(model.components().len(), 1);
+        model.redo();
+        assert!(model.booleanization().is_some());
+
+        // Copies keep the mapping until they are modified
+        let mut copy = QModel::clone(&model);
+        assert!(copy.booleanization().is_some());
+        let b1 = copy.get_handle("A_b1").unwrap();
+        copy.set_rule_from_str(b1, 1, "true").unwrap();
+        assert!(copy.booleanization().is_none());
+        assert!(model.booleanization().is_some());
+    }
+}
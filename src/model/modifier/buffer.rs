@@ -1,3 +1,4 @@
+use itertools::Itertools;
 use std::collections::HashMap;
 
 use crate::func::expr::{AtomReplacer, Expr};
@@ -28,6 +29,7 @@ enum BufferRef {
     Split(HashMap<usize, usize>),
 
     /// Custom selection of buffering
+    #[allow(dead_code)]
     Selected(HashMap<usize, BufferSelection>),
 }
 
@@ -61,12 +63,21 @@ impl BufferRef {
             BufferRef::Split(m) => {
                 m.entry(target)
                     .or_insert_with(|| create_buffer(model, regulator));
-                return m.get(&target).copied();
+                m.get(&target).copied()
             }
             BufferRef::Selected(m) => m.get_mut(&target).map(|bs| bs.get_buffer(model, regulator)),
         }
     }
 
+    fn name(&self) -> &'static str {
+        match self {
+            BufferRef::Direct => "direct",
+            BufferRef::Delayed(_) => "delay",
+            BufferRef::Split(_) => "split",
+            BufferRef::Selected(_) => "selected",
+        }
+    }
+
     fn split() -> Self {
         Self::Split(HashMap::new())
     }
@@ -87,7 +98,7 @@ impl<'a> BufferConfig<'a> {
 
     fn get_buffer(&mut self, regulator: &Variable) -> Option<usize> {
         if let Some(b) = self.get_buffer_component(regulator.component) {
-            Some(self.model.ensure_threshold(b, regulator.value))
+            self.model.ensure_threshold(b, regulator.value).ok()
         } else {
             None
         }
@@ -127,6 +138,15 @@ impl<'a> BufferConfig<'a> {
     }
 
     pub fn apply(&mut self) {
+        let started = self.model.start_edit("buffers");
+        let mut arguments: Vec<String> = self
+            .map
+            .iter()
+            .map(|(uid, mode)| format!("{}={}", self.model.get_name(*uid), mode.name()))
+            .sorted()
+            .collect();
+        arguments.insert(0, format!("{:?}", self.strategy));
+        self.model.add_modification("buffer", arguments);
         let components: Vec<usize> = self.model.components().copied().collect();
         for cid in components {
             self.set_target(cid);
@@ -141,6 +161,7 @@ impl<'a> BufferConfig<'a> {
             // Apply the new rule
             Rc::make_mut(&mut self.model.rules).replace(cid, rule);
         }
+        self.model.end_edit(started);
     }
 
     fn set_target(&mut self, target: usize) {
@@ -157,10 +178,8 @@ impl<'a> AtomReplacer for BufferConfig<'a> {
             Some(v) => *v,
         };
 
-        match self.get_buffer(&var) {
-            None => None,
-            Some(b) => Some(if value { Expr::ATOM(b) } else { Expr::NATOM(b) }),
-        }
+        self.get_buffer(&var)
+            .map(|b| if value { Expr::ATOM(b) } else { Expr::NATOM(b) })
     }
 }
 
@@ -169,11 +188,10 @@ fn create_buffer(model: &mut QModel, src: usize) -> usize {
     let buf_id = model.add_component(&format!("_b_{}", model.get_name(src)));
 
     let variables = model.get_variables(src).clone();
-    let mut value = 1;
-    for var in variables {
-        model.ensure_threshold(buf_id, value);
+    for (idx, var) in variables.into_iter().enumerate() {
+        let value = idx + 1;
+        model.ensure_threshold(buf_id, value).unwrap();
         model.push_cpt_rule(buf_id, value, Formula::from(Expr::ATOM(var)));
-        value += 1;
     }
 
     buf_id
@@ -0,0 +1,327 @@
+//! Extract a submodel from a selection of components.
+//!
+//! Components can be selected by name, by regular expression, or as the upstream or
+//! downstream neighbourhood of a component in the regulatory graph. All other components are
+//! removed, except the regulators of selected components, which are kept as inputs. Inputs are
+//! free (they keep their current level) or fixed at a chosen level.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use regex::Regex;
+
+use crate::func::expr::Expr;
+use crate::helper::error::{CanFail, GenericError};
+use crate::model::rule::ComponentRules;
+use crate::model::QModel;
+use crate::variables::{parse_level_assignment, GroupedVariables};
+
+/// Select the components of a submodel and remove the others
+pub struct Extractor<'a> {
+    model: &'a mut QModel,
+    selected: HashSet<usize>,
+    input_level: Option<usize>,
+    fixed: HashMap<usize, usize>,
+}
+
+impl<'a> Extractor<'a> {
+    pub fn new(model: &'a mut QModel) -> Self {
+        Extractor {
+            model,
+            selected: HashSet::new(),
+            input_level: None,
+            fixed: HashMap::new(),
+        }
+    }
+
+    /// Select a component by name
+    pub fn select(&mut self, name: &str) -> CanFail<GenericError> {
+        let cid = self.model.get_handle_res(name)?;
+        self.selected.insert(cid);
+        Ok(())
+    }
+
+    /// Select all components with a name matching a regular expression.
+    ///
+    /// Returns the number of matching components.
+    pub fn select_regex(&mut self, pattern: &str) -> Result<usize, GenericError> {
+        let re = Regex::new(pattern)
+            .map_err(|e| GenericError::new(format!("Invalid regular expression: {}", e)))?;
+        let matching: Vec<usize> = self
+            .model
+            .components()
+            .filter(|cid| re.is_match(self.model.get_name(**cid)))
+            .copied()
+            .collect();
+        let count = matching.len();
+        self.selected.extend(matching);
+        Ok(count)
+    }
+
+    /// Select a component and its regulators, up to a given depth
+    pub fn select_upstream(&mut self, name: &str, depth: usize) -> CanFail<GenericError> {
+        self.select_neighbourhood(name, depth, true)
+    }
+
+    /// Select a component and its targets, up to a given depth
+    pub fn select_downstream(&mut self, name: &str, depth: usize) -> CanFail<GenericError> {
+        self.select_neighbourhood(name, depth, false)
+    }
+
+    fn select_neighbourhood(
+        &mut self,
+        name: &str,
+        depth: usize,
+        upstream: bool,
+    ) -> CanFail<GenericError> {
+        let cid = self.model.get_handle_res(name)?;
+        let mut neighbours: HashMap<usize, Vec<usize>> = HashMap::new();
+        for i in self.model.interaction_graph() {
+            let (from, to) = if upstream {
+                (i.target, i.source)
+            } else {
+                (i.source, i.target)
+            };
+            neighbours.entry(from).or_default().push(to);
+        }
+
+        let mut reached: HashSet<usize> = HashSet::new();
+        reached.insert(cid);
+        let mut frontier = vec![cid];
+        for _ in 0..depth {
+            frontier = frontier
+                .iter()
+                .filter_map(|c| neighbours.get(c))
+                .flatten()
+                .filter(|c| reached.insert(**c))
+                .copied()
+                .collect();
+            if frontier.is_empty() {
+                break;
+            }
+        }
+        self.selected.extend(reached);
+        Ok(())
+    }
+
+    /// Fix all inputs at a given level, or leave them free.
+    ///
+    /// The level must be valid for all inputs which are not fixed individually.
+    pub fn set_input_level(&mut self, level: Option<usize>) -> &mut Self {
+        self.input_level = level;
+        self
+    }
+
+    /// Fix the level of a specific input (``A=1``)
+    pub fn fix_input(&mut self, s: &str) -> CanFail<GenericError> {
+        let (cid, level) = parse_level_assignment(self.model, s)?;
+        self.fixed.insert(cid, level);
+        Ok(())
+    }
+
+    /// Regulators of the selected components which are not selected
+    fn find_inputs(&self) -> HashSet<usize> {
+        let mut inputs = HashSet::new();
+        for cid in self.selected.iter() {
+            let rules = match self.model.rules.get(*cid) {
+                None => continue,
+                Some(r) => r,
+            };
+            for assign in rules.assignments() {
+                let expr: Rc<Expr> = assign.formula.convert_as();
+                let literals = expr.get_literals();
+                for vid in literals.positive().iter().chain(literals.negative().iter()) {
+                    if let Some(var) = self.model.get_component_value(vid) {
+                        if !self.selected.contains(&var.component) {
+                            inputs.insert(var.component);
+                        }
+                    }
+                }
+            }
+        }
+        inputs
+    }
+
+    /// Remove the components which are not selected and turn outside regulators into inputs.
+    ///
+    /// Returns the number of removed components. The model is left unchanged if the selection
+    /// is empty or if an input is fixed at an invalid level.
+    pub fn apply(&mut self) -> Result<usize, GenericError> {
+        if self.selected.is_empty() {
+            return Err(GenericError::new("No component selected".to_owned()));
+        }
+
+        let inputs = self.find_inputs();
+        for cid in self.fixed.keys() {
+            if !inputs.contains(cid) {
+                let name = self.model.get_name(*cid);
+                return Err(GenericError::new(format!("{} is not an input", name)));
+            }
+        }
+        for cid in inputs.iter() {
+            let level = match self.fixed.get(cid).or(self.input_level.as_ref()) {
+                None => continue,
+                Some(level) => *level,
+            };
+            if level > self.model.get_variables(*cid).len() {
+                return Err(GenericError::new(format!(
+                    "Invalid level for {}: {}",
+                    self.model.get_name(*cid),
+                    level
+                )));
+            }
+        }
+
+        let started = self.model.start_edit("extraction");
+        let arguments = self.arguments();
+        self.model.add_modification("extract", arguments);
+
+        let components: Vec<usize> = self.model.components().copied().collect();
+        let mut removed = 0;
+        for cid in components {
+            if self.selected.contains(&cid) {
+                continue;
+            }
+            if !inputs.contains(&cid) {
+                self.model.discard_component(cid);
+                removed += 1;
+                continue;
+            }
+
+            match self.fixed.get(&cid).or(self.input_level.as_ref()) {
+                Some(level) => self
+                    .model
+                    .lock_component(cid, *level)
+                    .expect("Input levels are checked before the extraction"),
+                None => {
+                    // Each threshold of a free input keeps its current value
+                    let variables = self.model.get_variables(cid).clone();
+                    let rules =
+                        ComponentRules::from_thresholds(variables.into_iter().map(Expr::ATOM));
+                    Rc::make_mut(&mut self.model.rules).replace(cid, rules);
+                }
+            }
+        }
+
+        self.model.end_edit(started);
+        Ok(removed)
+    }
+
+    fn arguments(&self) -> Vec<String> {
+        let mut selected: Vec<&str> = self
+            .selected
+            .iter()
+            .map(|cid| self.model.get_name(*cid))
+            .collect();
+        selected.sort_unstable();
+        let mut arguments: Vec<String> = selected.into_iter().map(str::to_owned).collect();
+        if let Some(level) = self.input_level {
+            arguments.push(format!("--input-level={}", level));
+        }
+        let mut fixed: Vec<String> = self
+            .fixed
+            .iter()
+            .map(|(cid, level)| format!("--fix={}={}", self.model.get_name(*cid), level))
+            .collect();
+        fixed.sort();
+        arguments.extend(fixed);
+        arguments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::model::modifier::extract::Extractor;
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    fn model() -> QModel {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        let d = model.ensure("D");
+        let e = model.ensure("E");
+        model.ensure_threshold(a, 2).unwrap();
+        model.set_rule_from_str(a, 1, "E").unwrap();
+        model.set_rule_from_str(a, 2, "E & !D").unwrap();
+        model.set_rule_from_str(b, 1, "A:2").unwrap();
+        model.set_rule_from_str(c, 1, "B & !A").unwrap();
+        model.set_rule_from_str(d, 1, "C").unwrap();
+        model.set_rule_from_str(e, 1, "E").unwrap();
+        model
+    }
+
+    #[test]
+    fn extract_neighbourhood() {
+        let mut model = model();
+        let a = model.get_handle("A").unwrap();
+        let a2 = model.get_variable(a, 2).unwrap();
+        let d = model.get_handle("D").unwrap();
+        let e = model.get_handle("E").unwrap();
+
+        // Upstream of C at depth 1: B and A, with D and E as free inputs of A
+        let mut extractor = Extractor::new(&mut model);
+        extractor.select_upstream("C", 1).unwrap();
+        assert_eq!(extractor.apply().unwrap(), 0);
+        assert_eq!(model.get_var_rule(d), Expr::ATOM(d));
+        assert_eq!(model.get_var_rule(e), Expr::ATOM(e));
+        model.undo();
+        assert!(model
+            .get_var_rule(d)
+            .is_equivalent(&Expr::ATOM(model.get_handle("C").unwrap())));
+
+        let mut extractor = Extractor::new(&mut model);
+        extractor.select_downstream("B", 1).unwrap();
+        extractor.set_input_level(Some(2));
+        assert_eq!(extractor.apply().unwrap(), 2);
+        assert_eq!(model.components().len(), 3);
+        assert_eq!(model.get_var_rule(a2), Expr::ATOM(a));
+        assert_eq!(model.get_var_rule(a), Expr::TRUE);
+        model.undo();
+
+        // Free inputs keep their level
+        let mut extractor = Extractor::new(&mut model);
+        assert_eq!(extractor.select_regex("^[BC]$").unwrap(), 2);
+        extractor.apply().unwrap();
+        assert_eq!(model.components().len(), 3);
+        let rules = model.rules.get(a).unwrap();
+        assert!(rules
+            .raw_variable_formula(1)
+            .is_equivalent(&Expr::ATOM(a).or(&Expr::ATOM(a2))));
+        assert!(rules.raw_variable_formula(2).is_equivalent(&Expr::ATOM(a2)));
+    }
+
+    #[test]
+    fn extract_errors() {
+        let mut model = model();
+        assert!(Extractor::new(&mut model).apply().is_err());
+        assert!(Extractor::new(&mut model).select("X").is_err());
+        assert!(Extractor::new(&mut model).select_regex("(").is_err());
+
+        let mut extractor = Extractor::new(&mut model);
+        extractor.select("C").unwrap();
+        extractor.fix_input("D=1").unwrap();
+        assert!(extractor.apply().is_err());
+
+        let mut extractor = Extractor::new(&mut model);
+        extractor.select("C").unwrap();
+        assert!(extractor.fix_input("A=3").is_err());
+        assert!(extractor.fix_input("A").is_err());
+        assert_eq!(model.components().len(), 5);
+
+        // The global input level is validated for each input, unless it is fixed separately
+        let mut extractor = Extractor::new(&mut model);
+        extractor.select("C").unwrap();
+        extractor.set_input_level(Some(2));
+        assert!(extractor.apply().is_err());
+        assert_eq!(model.components().len(), 5);
+
+        let mut extractor = Extractor::new(&mut model);
+        extractor.select("C").unwrap();
+        extractor.set_input_level(Some(2));
+        extractor.fix_input("B=1").unwrap();
+        assert_eq!(extractor.apply().unwrap(), 2);
+    }
+}
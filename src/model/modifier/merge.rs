@@ -0,0 +1,432 @@
+//! Merge the components of a second model into the current one.
+//!
+//! Components with the same name in both models are shared, unless a restricted list of
+//! shared components is selected. Other components are imported with an optional prefix,
+//! and renamed if their name is already used. The rules of shared components are combined
+//! according to a merge policy, and their layout is kept if it is defined in the current model.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::func::expr::{AtomReplacer, Expr};
+use crate::helper::error::GenericError;
+use crate::model::rule::ComponentRules;
+use crate::model::QModel;
+use crate::variables::{is_valid_name, GroupedVariables};
+
+/// Combination of the rules of shared components
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MergePolicy {
+    /// Keep the rule of the current model
+    LEFT,
+    /// Use the rule of the imported model
+    RIGHT,
+    /// A threshold is reached if it is reached in any of the models
+    OR,
+    /// A threshold is reached if it is reached in both models
+    AND,
+    /// Refuse to merge models with different rules for shared components
+    FAIL,
+}
+
+/// Import the components of a model into the current one
+pub struct Merger<'a> {
+    model: &'a mut QModel,
+    policy: MergePolicy,
+    prefix: String,
+    shared: Option<HashSet<String>>,
+}
+
+/// Map the variables of the imported model to the variables of the current model
+struct Translation {
+    mapping: HashMap<usize, usize>,
+}
+
+impl AtomReplacer for Translation {
+    fn replace(&mut self, var: usize, value: bool) -> Option<Expr> {
+        self.mapping.get(&var).map(|v| {
+            if value {
+                Expr::ATOM(*v)
+            } else {
+                Expr::NATOM(*v)
+            }
+        })
+    }
+}
+
+impl Translation {
+    /// Map the variables of matched components, and use fresh identifiers for the others
+    fn new(model: &QModel, other: &QModel, components: &HashMap<usize, usize>) -> Self {
+        let offset = model
+            .components()
+            .flat_map(|cid| model.get_variables(*cid).iter())
+            .max()
+            .map(|v| v + 1)
+            .unwrap_or(0);
+        let mut mapping = HashMap::new();
+        let variables = other
+            .components()
+            .flat_map(|cid| other.get_variables(*cid).iter());
+        for vid in variables {
+            let var = match other.get_component_value(*vid) {
+                None => continue,
+                Some(v) => v,
+            };
+            let target = components
+                .get(&var.component)
+                .and_then(|cid| model.get_variable(*cid, var.value))
+                .unwrap_or(offset + vid);
+            mapping.insert(*vid, target);
+        }
+        Translation { mapping }
+    }
+
+    fn expr(&mut self, e: Expr) -> Expr {
+        e.replace_variables(self).unwrap_or(e)
+    }
+
+    fn rules(&mut self, rules: &ComponentRules) -> ComponentRules {
+        let mut rules = rules.clone();
+        rules.map_assignments(|assign| {
+            let expr: Rc<Expr> = assign.formula.convert_as();
+            if let Some(e) = expr.replace_variables(self) {
+                assign.formula.set(e);
+            }
+        });
+        rules
+    }
+}
+
+impl FromStr for MergePolicy {
+    type Err = GenericError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "left" => Ok(MergePolicy::LEFT),
+            "right" => Ok(MergePolicy::RIGHT),
+            "or" => Ok(MergePolicy::OR),
+            "and" => Ok(MergePolicy::AND),
+            "fail" => Ok(MergePolicy::FAIL),
+            _ => Err(GenericError::new(format!("Unknown merge policy: {}", s))),
+        }
+    }
+}
+
+impl<'a> Merger<'a> {
+    pub fn new(model: &'a mut QModel) -> Self {
+        Merger {
+            model,
+            policy: MergePolicy::FAIL,
+            prefix: String::new(),
+            shared: None,
+        }
+    }
+
+    pub fn set_policy(&mut self, policy: MergePolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Add a prefix to the names of imported components which are not shared
+    pub fn set_prefix(&mut self, prefix: &str) -> &mut Self {
+        self.prefix = prefix.to_owned();
+        self
+    }
+
+    /// Select a shared component.
+    ///
+    /// If some components are selected, other components with the same name in both
+    /// models are considered as distinct.
+    pub fn share(&mut self, name: &str) -> &mut Self {
+        self.shared
+            .get_or_insert_with(HashSet::new)
+            .insert(name.to_owned());
+        self
+    }
+
+    fn is_shared(&self, name: &str) -> bool {
+        match &self.shared {
+            None => true,
+            Some(s) => s.contains(name),
+        }
+    }
+
+    /// Import the components of another model and combine the rules of shared components.
+    ///
+    /// Returns the number of imported components which were not shared. The current model is
+    /// left unchanged if the name of an imported component is invalid or if the rules of shared
+    /// components are conflicting with the FAIL policy.
+    pub fn apply(&mut self, other: &QModel) -> Result<usize, GenericError> {
+        let mut components: HashMap<usize, usize> = HashMap::new();
+        let mut imported = vec![];
+        for ocid in other.components() {
+            let name = other.get_name(*ocid);
+            match self.model.get_handle(name) {
+                Some(cid) if self.is_shared(name) => {
+                    components.insert(*ocid, cid);
+                }
+                _ => {
+                    let new_name = format!("{}{}", self.prefix, name);
+                    if !is_valid_name(&new_name) {
+                        return Err(GenericError::new(format!("Invalid name: {}", new_name)));
+                    }
+                    imported.push(*ocid);
+                }
+            }
+        }
+
+        if self.policy == MergePolicy::FAIL {
+            self.check_conflicts(other, &components)?;
+        }
+
+        let started = self.model.start_edit("merge");
+        let arguments = self.arguments(other);
+        self.model.add_modification("merge", arguments);
+
+        // Create the imported components and the missing thresholds
+        for ocid in imported.iter() {
+            let name = format!("{}{}", self.prefix, other.get_name(*ocid));
+            let name = self.model.find_free_name(&name).unwrap_or(name);
+            let cid = self.model.ensure(&name);
+            components.insert(*ocid, cid);
+        }
+        for (ocid, cid) in components.iter() {
+            let max = other.get_variables(*ocid).len();
+            if max > 0 {
+                self.model
+                    .ensure_threshold(*cid, max)
+                    .expect("The levels of the merged model are valid");
+            }
+        }
+
+        let mut translation = Translation::new(self.model, other, &components);
+        for ocid in other.components() {
+            let cid = components[ocid];
+            let rules = other
+                .rules
+                .get(*ocid)
+                .filter(|r| r.assignments().len() > 0)
+                .map(|r| translation.rules(r));
+            let rules = match (rules, imported.contains(ocid)) {
+                (None, _) => None,
+                (Some(r), true) => Some(r),
+                (Some(r), false) => self.combine(cid, r),
+            };
+            if let Some(r) = rules {
+                Rc::make_mut(&mut self.model.rules).replace(cid, r);
+            }
+
+            if let Some(bb) = other.get_bounding_box(*ocid) {
+                if self.model.get_bounding_box(cid).is_none() {
+                    self.model.set_bounding_box(cid, *bb);
+                }
+            }
+        }
+
+        self.model.end_edit(started);
+        Ok(imported.len())
+    }
+
+    /// Combine the translated rules of an imported component with the rules of a shared
+    /// component, returns None if the current rules are kept
+    fn combine(&self, cid: usize, rules: ComponentRules) -> Option<ComponentRules> {
+        let current = match self.model.rules.get(cid) {
+            Some(r) if r.assignments().len() > 0 => r,
+            _ => return Some(rules),
+        };
+
+        // Keep the current rules if they are equivalent
+        let max = self.model.get_variables(cid).len();
+        let equivalent = (1..=max).all(|value| {
+            let right = rules.raw_variable_formula(value);
+            current.raw_variable_formula(value).is_equivalent(&right)
+        });
+        if equivalent {
+            return None;
+        }
+
+        let conditions = (1..=max).map(|value| {
+            let left = current.raw_variable_formula(value);
+            let right = rules.raw_variable_formula(value);
+            let e = match self.policy {
+                MergePolicy::OR => left.or(&right),
+                _ => left.and(&right),
+            };
+            e.simplify().unwrap_or(e)
+        });
+        match self.policy {
+            MergePolicy::LEFT | MergePolicy::FAIL => None,
+            MergePolicy::RIGHT => Some(rules),
+            MergePolicy::OR | MergePolicy::AND => Some(ComponentRules::from_thresholds(conditions)),
+        }
+    }
+
+    fn check_conflicts(
+        &self,
+        other: &QModel,
+        components: &HashMap<usize, usize>,
+    ) -> Result<(), GenericError> {
+        let mut translation = Translation::new(self.model, other, components);
+        let mut conflicts = vec![];
+        for (ocid, cid) in components.iter() {
+            let (current, rules) = match (self.model.rules.get(*cid), other.rules.get(*ocid)) {
+                (Some(c), Some(r)) if c.assignments().len() > 0 && r.assignments().len() > 0 => {
+                    (c, r)
+                }
+                _ => continue,
+            };
+            let max = self
+                .model
+                .get_variables(*cid)
+                .len()
+                .max(other.get_variables(*ocid).len());
+            let conflict = (1..=max).any(|value| {
+                let e = translation.expr(rules.raw_variable_formula(value));
+                !current.raw_variable_formula(value).is_equivalent(&e)
+            });
+            if conflict {
+                conflicts.push(self.model.get_name(*cid).to_owned());
+            }
+        }
+
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        conflicts.sort();
+        Err(GenericError::new(format!(
+            "Conflicting rules for shared components: {}",
+            conflicts.join(", ")
+        )))
+    }
+
+    fn arguments(&self, other: &QModel) -> Vec<String> {
+        let provenance = other.provenance();
+        let source = match provenance.origin().source() {
+            Some(s) => s.to_owned(),
+            None => provenance.variant(),
+        };
+        let mut arguments = vec![source, format!("--policy={:?}", self.policy).to_lowercase()];
+        if !self.prefix.is_empty() {
+            arguments.push(format!("--prefix={}", self.prefix));
+        }
+        if let Some(shared) = &self.shared {
+            let mut shared: Vec<&str> = shared.iter().map(|s| s.as_str()).collect();
+            shared.sort_unstable();
+            arguments.push(format!("--shared={}", shared.join(",")));
+        }
+        arguments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::model::modifier::merge::{MergePolicy, Merger};
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    fn models() -> (QModel, QModel) {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let x = model.ensure("X");
+        model.set_rule_from_str(a, 1, "B").unwrap();
+        model.set_rule_from_str(b, 1, "!A").unwrap();
+        model.set_rule_from_str(x, 1, "A").unwrap();
+
+        let mut module = QModel::default();
+        let b = module.ensure("B");
+        let c = module.ensure("C");
+        let x = module.ensure("X");
+        module.set_rule_from_str(b, 1, "C").unwrap();
+        module.set_rule_from_str(c, 1, "X:2").unwrap();
+        module.set_rule_from_str(x, 1, "C").unwrap();
+        module.set_rule_from_str(x, 2, "C & B").unwrap();
+        (model, module)
+    }
+
+    #[test]
+    fn merge_models() {
+        let (mut model, module) = models();
+        let a = model.get_handle("A").unwrap();
+        let b = model.get_handle("B").unwrap();
+
+        // Conflicting rules are refused by default
+        assert!(Merger::new(&mut model).apply(&module).is_err());
+        assert_eq!(model.components().len(), 3);
+        assert!(Merger::new(&mut model)
+            .set_prefix("1")
+            .share("B")
+            .apply(&module)
+            .is_err());
+
+        // Only share B: the imported X is renamed
+        let mut merger = Merger::new(&mut model);
+        merger.set_policy(MergePolicy::OR).share("B");
+        assert_eq!(merger.apply(&module).unwrap(), 2);
+        assert_eq!(model.components().len(), 5);
+        let c = model.get_handle("C").unwrap();
+        let x1 = model.get_handle("X_1").unwrap();
+        let x12 = model.get_variable(x1, 2).unwrap();
+        assert_eq!(model.get_var_rule(c), Expr::ATOM(x12));
+        assert!(model
+            .get_var_rule(b)
+            .is_equivalent(&Expr::NATOM(a).or(&Expr::ATOM(c))));
+        assert!(model.get_var_rule(x12).get_literals().is_fixed_at(b, true));
+    }
+
+    #[test]
+    fn merge_policies() {
+        let (mut model, module) = models();
+        let a = model.get_handle("A").unwrap();
+        let b = model.get_handle("B").unwrap();
+        let x = model.get_handle("X").unwrap();
+
+        let mut merger = Merger::new(&mut model);
+        merger.set_policy(MergePolicy::AND).set_prefix("m_");
+        merger.share("B").share("X");
+        assert_eq!(merger.apply(&module).unwrap(), 1);
+        let c = model.get_handle("m_C").unwrap();
+        let x2 = model.get_variable(x, 2).unwrap();
+        assert!(model
+            .get_var_rule(b)
+            .is_equivalent(&Expr::NATOM(a).and(&Expr::ATOM(c))));
+        assert_eq!(model.get_var_rule(x2), Expr::FALSE);
+        model.undo();
+
+        let mut merger = Merger::new(&mut model);
+        merger.set_policy(MergePolicy::RIGHT);
+        merger.apply(&module).unwrap();
+        let c = model.get_handle("C").unwrap();
+        assert_eq!(model.get_var_rule(b), Expr::ATOM(c));
+        model.undo();
+
+        let mut merger = Merger::new(&mut model);
+        merger.set_policy(MergePolicy::LEFT);
+        merger.apply(&module).unwrap();
+        assert_eq!(model.get_var_rule(b), Expr::NATOM(a));
+        assert!("xor".parse::<MergePolicy>().is_err());
+    }
+
+    #[test]
+    fn merge_normalized_names() {
+        let (mut model, _) = models();
+        let mut module = QModel::default();
+        let n = module.ensure("2 n");
+        module.set_rule_from_str(n, 1, "A").unwrap();
+        assert_eq!(module.get_name(n), "_2_n");
+
+        // Normalized names and prefixes starting with an underscore are valid identifiers
+        let mut merger = Merger::new(&mut model);
+        merger.share("A");
+        assert_eq!(merger.apply(&module).unwrap(), 1);
+        assert!(model.get_handle("_2_n").is_some());
+        model.undo();
+
+        let mut merger = Merger::new(&mut model);
+        merger.set_prefix("_").share("A");
+        assert_eq!(merger.apply(&module).unwrap(), 1);
+        assert!(model.get_handle("__2_n").is_some());
+    }
+}
@@ -0,0 +1,348 @@
+//! Merge groups of Boolean components encoding the levels of a multivalued component.
+//!
+//! This is the reverse of the booleanization: the members of a group (``A_1``, ``A_2``...)
+//! are the successive thresholds of a single component. Groups are declared explicitly or
+//! detected from the names of the components (``A_1``, ``A_2`` or ``A_b1``, ``A_b2``).
+//!
+//! The encoding must be order-preserving: in any state respecting the order of the thresholds,
+//! a member can only be targeted if the previous member is also targeted. The first member is
+//! kept as the merged component, the other members become its thresholds.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::func::expr::{AtomReplacer, Expr, Operator};
+use crate::func::Formula;
+use crate::helper::error::{CanFail, GenericError};
+use crate::model::QModel;
+use crate::variables::{is_valid_name, GroupedVariables, MAX_LEVEL};
+
+static RE_MEMBER: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(.+)_b?([1-9][0-9]*)$").unwrap());
+
+/// A multivalued component and the Boolean components encoding its thresholds
+struct Group {
+    name: String,
+    members: Vec<usize>,
+}
+
+/// Collect groups of Boolean components and merge them into multivalued components
+pub struct ThresholdGroups<'a> {
+    model: &'a mut QModel,
+    groups: Vec<Group>,
+    skipped: Vec<GenericError>,
+}
+
+/// Replace the members of the groups with the thresholds of the merged components
+struct Translation {
+    mapping: HashMap<usize, usize>,
+}
+
+impl AtomReplacer for Translation {
+    fn replace(&mut self, var: usize, value: bool) -> Option<Expr> {
+        self.mapping.get(&var).map(|vid| {
+            if value {
+                Expr::ATOM(*vid)
+            } else {
+                Expr::NATOM(*vid)
+            }
+        })
+    }
+}
+
+impl<'a> ThresholdGroups<'a> {
+    pub fn new(model: &'a mut QModel) -> Self {
+        ThresholdGroups {
+            model,
+            groups: vec![],
+            skipped: vec![],
+        }
+    }
+
+    /// Declare a group of Boolean components, from the lowest to the highest threshold.
+    ///
+    /// The merged component takes the given name, which can be the name of a member.
+    pub fn add_group(&mut self, name: &str, members: &[&str]) -> CanFail<GenericError> {
+        if members.len() < 2 || members.len() > MAX_LEVEL {
+            return Err(GenericError::new(format!(
+                "{} should be encoded by 2 to {} components",
+                name, MAX_LEVEL
+            )));
+        }
+        let mut cids = Vec::with_capacity(members.len());
+        for member in members {
+            let cid = self.model.get_handle_res(member)?;
+            if self.model.get_component_value(cid).map(|v| v.value) != Some(1)
+                || self.model.get_variables(cid).len() != 1
+            {
+                return Err(GenericError::new(format!("{} is not Boolean", member)));
+            }
+            if cids.contains(&cid) || self.groups.iter().any(|g| g.members.contains(&cid)) {
+                return Err(GenericError::new(format!(
+                    "{} is used in several groups",
+                    member
+                )));
+            }
+            cids.push(cid);
+        }
+        if !is_valid_name(name) {
+            return Err(GenericError::new(format!(
+                "Invalid component name: {}",
+                name
+            )));
+        }
+        let used = self.model.get_handle(name).filter(|c| !cids.contains(c));
+        if used.is_some() || self.groups.iter().any(|g| g.name == name) {
+            return Err(GenericError::new(format!("{} is already used", name)));
+        }
+        self.groups.push(Group {
+            name: name.to_owned(),
+            members: cids,
+        });
+        Ok(())
+    }
+
+    /// Detect groups from the names of Boolean components, returns the number of new groups.
+    ///
+    /// A group is formed by components sharing a prefix and numbered from 1 without gaps.
+    /// Components which are already in a group and groups whose prefix is the name of
+    /// another component are ignored. Detected groups whose encoding is not order-preserving
+    /// are skipped, see ``skipped``.
+    pub fn detect(&mut self) -> usize {
+        let mut candidates: BTreeMap<String, BTreeMap<usize, String>> = BTreeMap::new();
+        for cid in self.model.components() {
+            let name = self.model.get_name(*cid);
+            if let Some(cap) = RE_MEMBER.captures(name) {
+                let index = match cap[2].parse() {
+                    Ok(i) => i,
+                    Err(_) => continue,
+                };
+                candidates
+                    .entry(cap[1].to_owned())
+                    .or_default()
+                    .insert(index, name.to_owned());
+            }
+        }
+
+        let mut count = 0;
+        for (prefix, members) in candidates.iter() {
+            let consecutive = members.keys().zip(1..).all(|(i, expected)| *i == expected);
+            if members.len() < 2 || !consecutive {
+                continue;
+            }
+            let members: Vec<&str> = members.values().map(|n| n.as_str()).collect();
+            if self.add_group(prefix, &members).is_err() {
+                continue;
+            }
+            let group = self.groups.last().unwrap();
+            if let Err(e) = self.check_group(group, &self.ordered_states()) {
+                self.groups.pop();
+                self.skipped.push(e);
+                continue;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Reasons for skipping the detected groups which are not order-preserving
+    pub fn skipped(&self) -> &[GenericError] {
+        &self.skipped
+    }
+
+    /// The states respecting the order of the members in all groups
+    fn ordered_states(&self) -> Expr {
+        let mut constraints = vec![];
+        for group in self.groups.iter() {
+            for w in group.members.windows(2) {
+                constraints.push(Expr::ATOM(w[0]).or(&Expr::NATOM(w[1])));
+            }
+        }
+        Operator::AND.join(&mut constraints.into_iter())
+    }
+
+    /// Check that the rules of the members of each group preserve the order of thresholds
+    pub fn check(&self) -> CanFail<GenericError> {
+        let ordered = self.ordered_states();
+        for group in self.groups.iter() {
+            self.check_group(group, &ordered)?;
+        }
+        Ok(())
+    }
+
+    /// Check the order of thresholds for one group, in the states respecting the order of all groups
+    fn check_group(&self, group: &Group, ordered: &Expr) -> CanFail<GenericError> {
+        for w in group.members.windows(2) {
+            let lower = self.model.get_var_rule(w[0]);
+            let higher = self.model.get_var_rule(w[1]);
+            let broken = ordered.and(&higher).and(&lower.not());
+            if !broken.prime_implicants().is_empty() {
+                return Err(GenericError::new(format!(
+                    "The encoding of {} is not order-preserving: {} can be activated without {}",
+                    group.name,
+                    self.model.get_name(w[1]),
+                    self.model.get_name(w[0])
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge all groups into multivalued components, returns the number of merged groups.
+    ///
+    /// The model is left unchanged if the encoding of a group is not order-preserving.
+    pub fn apply(&mut self) -> Result<usize, GenericError> {
+        if self.groups.is_empty() {
+            return Ok(0);
+        }
+        self.check()?;
+
+        let started = self.model.start_edit("multivalued");
+        let arguments = self
+            .groups
+            .iter()
+            .map(|g| {
+                let names: Vec<&str> = g.members.iter().map(|c| self.model.get_name(*c)).collect();
+                format!("{}={}", g.name, names.join(","))
+            })
+            .collect();
+        self.model.add_modification("multivalued", arguments);
+
+        // Save the rules of the members before removing the higher ones
+        let conditions: Vec<Vec<Expr>> = self
+            .groups
+            .iter()
+            .map(|g| {
+                g.members
+                    .iter()
+                    .map(|c| self.model.get_var_rule(*c))
+                    .collect()
+            })
+            .collect();
+        let mut mapping = HashMap::new();
+        for group in self.groups.iter() {
+            let cid = group.members[0];
+            for member in group.members.iter().skip(1) {
+                self.model.discard_component(*member);
+            }
+            if self.model.get_name(cid) != group.name {
+                Rc::make_mut(&mut self.model.variables)
+                    .set_name(cid, &group.name)
+                    .expect("Names are checked when adding groups");
+            }
+            for (member, value) in group.members.iter().zip(1..) {
+                let vid = self
+                    .model
+                    .ensure_threshold(cid, value)
+                    .expect("Levels are checked when adding groups");
+                mapping.insert(*member, vid);
+            }
+        }
+
+        // Use the new thresholds in all rules
+        let mut translation = Translation { mapping };
+        let merged: HashSet<usize> = self.groups.iter().map(|g| g.members[0]).collect();
+        let rules = Rc::make_mut(&mut self.model.rules);
+        for cid in self.model.variables.components() {
+            if merged.contains(cid) {
+                rules.remove(*cid);
+                continue;
+            }
+            if let Some(rule) = rules.get_mut(*cid) {
+                rule.map_assignments(|assign| {
+                    let expr: Rc<Expr> = assign.formula.convert_as();
+                    if let Some(e) = expr.replace_variables(&mut translation) {
+                        assign.formula.set(e);
+                    }
+                });
+            }
+        }
+        for (group, conditions) in self.groups.iter().zip(conditions) {
+            let cid = group.members[0];
+            for (e, value) in conditions.into_iter().zip(1..) {
+                let e = e.replace_variables(&mut translation).unwrap_or(e);
+                self.model.push_cpt_rule(cid, value, Formula::from(e));
+            }
+        }
+
+        self.model.end_edit(started);
+        Ok(self.groups.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::model::modifier::booleanize::{BooleanEncoding, Booleanizer};
+    use crate::model::modifier::multivalued::ThresholdGroups;
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn merge_groups() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        model.set_rule_from_str(a, 1, "B").unwrap();
+        model.set_rule_from_str(a, 2, "!B").unwrap();
+        model.set_rule_from_str(b, 1, "A:2").unwrap();
+
+        for encoding in [BooleanEncoding::VANHAM, BooleanEncoding::ORDERED] {
+            let mut boolean = Booleanizer::new(&model).set_encoding(encoding).apply();
+            let mut groups = ThresholdGroups::new(&mut boolean);
+            assert_eq!(groups.detect(), 1);
+            assert_eq!(groups.apply().unwrap(), 1);
+
+            let names: Vec<&str> = boolean.variables().map(|v| boolean.get_name(*v)).collect();
+            assert_eq!(names, vec!["A", "A:2", "B"]);
+            let a = boolean.get_handle("A").unwrap();
+            let a2 = boolean.get_handle("A:2").unwrap();
+            let b = boolean.get_handle("B").unwrap();
+            assert!(boolean.get_var_rule(b).is_equivalent(&Expr::ATOM(a2)));
+            let expected = Expr::ATOM(a).and(&Expr::NATOM(b));
+            assert!(boolean.get_var_rule(a2).is_equivalent(&expected));
+            assert_eq!(
+                boolean.provenance().lineage()[1].arguments,
+                vec!["A=A_b1,A_b2"]
+            );
+        }
+    }
+
+    #[test]
+    fn check_encoding() {
+        let mut model = QModel::default();
+        let x = model.ensure("X_1");
+        let y = model.ensure("X_2");
+        model.set_rule_from_str(x, 1, "C").unwrap();
+        model.set_rule_from_str(y, 1, "D").unwrap();
+
+        let mut groups = ThresholdGroups::new(&mut model);
+        assert!(groups.add_group("X", &["X_1"]).is_err());
+        assert!(groups.add_group("C", &["X_1", "X_2"]).is_err());
+        groups.add_group("X", &["X_1", "X_2"]).unwrap();
+        assert!(groups.add_group("Y", &["X_2", "C"]).is_err());
+        assert!(groups.apply().is_err());
+        assert_eq!(model.get_name(y), "X_2");
+
+        // The second member is now only reachable from the first one
+        model.set_rule_from_str(y, 1, "D & C").unwrap();
+        let mut groups = ThresholdGroups::new(&mut model);
+        assert_eq!(groups.detect(), 1);
+        groups.apply().unwrap();
+        assert_eq!(model.get_name(x), "X");
+        assert_eq!(format!("{}", model), "X <- C\nX:2 <- (D & C)\n");
+
+        // Detected groups which are not order-preserving are skipped
+        let i1 = model.ensure("IL_1");
+        let i2 = model.ensure("IL_2");
+        model.set_rule_from_str(i1, 1, "C").unwrap();
+        model.set_rule_from_str(i2, 1, "D").unwrap();
+        let mut groups = ThresholdGroups::new(&mut model);
+        assert_eq!(groups.detect(), 0);
+        assert_eq!(groups.skipped().len(), 1);
+        assert_eq!(groups.apply().unwrap(), 0);
+        assert!(model.get_handle("IL_2").is_some());
+    }
+}
@@ -1,13 +1,22 @@
+//! Perturbations of components and interactions.
+//!
+//! Perturbations can be defined with a compact syntax, where the level of a component or of a
+//! regulator in the rules of a single target is given after a ``%`` sign:
+//! * ``A%0`` and ``A%1`` fix the level of a component,
+//! * ``A%1:2`` restricts the level of a component to a range,
+//! * ``A@B%0`` fixes the level of the regulator ``A`` in the rules of ``B``,
+//! * ``A:2@B%1`` fixes a specific threshold of a multivalued regulator.
+
 use crate::helper::error::{CanFail, GenericError};
 use crate::model::QModel;
-use crate::variables::GroupedVariables;
-use std::collections::{HashMap, HashSet};
+use crate::variables::{parse_level_assignment, GroupedVariables};
+use itertools::Itertools;
+use std::collections::HashMap;
 
 pub struct Perturbator<'a> {
     model: &'a mut QModel,
     regulators: HashMap<(usize, usize), bool>,
-    components: HashMap<usize, bool>,
-    target: Option<usize>,
+    components: HashMap<usize, (usize, usize)>,
 }
 
 impl<'a> Perturbator<'a> {
@@ -16,7 +25,6 @@ impl<'a> Perturbator<'a> {
             model,
             regulators: Default::default(),
             components: Default::default(),
-            target: None,
         }
     }
 
@@ -24,42 +32,317 @@ impl<'a> Perturbator<'a> {
         if let Some(idx) = s.find('@') {
             self.lock_regulator(&s[..idx], &s[idx + 1..], value)
         } else {
-            self.lock_component(&s, value)
+            self.lock_component(s, value)
         }
     }
 
+    /// Lock a regulator in the rules of a single target.
+    ///
+    /// The regulator can be a component (``A``) or a specific threshold of a
+    /// multivalued component (``A:2``).
     pub fn lock_regulator(&mut self, src: &str, tgt: &str, value: bool) -> CanFail<GenericError> {
-        let src = self.model.get_handle_res(src)?;
+        let src = self.get_threshold_handle(src)?;
         let tgt = self.model.get_handle_res(tgt)?;
+        self.set_regulator(src, tgt, value)
+    }
 
-        // FIXME: handle multivalued
-
-        self.regulators.insert((src, tgt), value);
+    /// Fix the level of a regulator in the rules of a single target
+    pub fn fix_regulator(&mut self, src: &str, tgt: &str, level: usize) -> CanFail<GenericError> {
+        let cid = self.model.get_handle_res(src)?;
+        let tgt = self.model.get_handle_res(tgt)?;
+        let variables = self.model.get_variables(cid);
+        if level > variables.len() {
+            return Err(invalid_level(src, level));
+        }
+        let lower = variables[..level].last().copied();
+        let higher = variables.get(level).copied();
+        if let Some(vid) = lower {
+            self.set_regulator(vid, tgt, true)?;
+        }
+        if let Some(vid) = higher {
+            self.set_regulator(vid, tgt, false)?;
+        }
         Ok(())
     }
 
+    /// Lock a component: knock-out (level 0) or knock-in (at least level 1)
     pub fn lock_component(&mut self, uid: &str, value: bool) -> CanFail<GenericError> {
-        let uid = self.model.get_handle_res(uid)?;
-        self.components.insert(uid, value);
+        let cid = self.model.get_handle_res(uid)?;
+        let max = self.model.get_variables(cid).len();
+        let range = if value { (1, max) } else { (0, 0) };
+        self.set_component(cid, range)
+    }
+
+    /// Restrict the level of a component to a range of values
+    pub fn restrict_component(
+        &mut self,
+        name: &str,
+        min: usize,
+        max: usize,
+    ) -> CanFail<GenericError> {
+        let cid = self.model.get_handle_res(name)?;
+        if max > self.model.get_variables(cid).len() {
+            return Err(invalid_level(name, max));
+        }
+        if min > max {
+            return Err(GenericError::new(format!(
+                "Invalid range for {}: {}..{}",
+                name, min, max
+            )));
+        }
+        self.set_component(cid, (min, max))
+    }
+
+    /// Fix the level of a component (``A=2``)
+    pub fn parse_fixed(&mut self, s: &str) -> CanFail<GenericError> {
+        let (cid, level) = parse_level_assignment(self.model, s)?;
+        self.set_component(cid, (level, level))
+    }
+
+    /// Restrict the level of a component to a range (``A=1..2``)
+    pub fn parse_range(&mut self, s: &str) -> CanFail<GenericError> {
+        let (name, range) = split_once(s, '=')?;
+        let (min, max) = range
+            .split_once("..")
+            .ok_or_else(|| GenericError::new(format!("Invalid perturbation: {}", s)))?;
+        self.restrict_component(name, parse_level(s, min)?, parse_level(s, max)?)
+    }
+
+    /// Select the range of a component, a component can only be perturbed once
+    fn set_component(&mut self, cid: usize, range: (usize, usize)) -> CanFail<GenericError> {
+        match self.components.get(&cid) {
+            Some(r) if *r != range => Err(GenericError::new(format!(
+                "Conflicting perturbations of {}",
+                self.model.get_name(cid)
+            ))),
+            _ => {
+                self.components.insert(cid, range);
+                Ok(())
+            }
+        }
+    }
+
+    /// Select the value of a regulator in a target, a regulator can only be perturbed once
+    fn set_regulator(&mut self, src: usize, tgt: usize, value: bool) -> CanFail<GenericError> {
+        match self.regulators.get(&(src, tgt)) {
+            Some(v) if *v != value => Err(GenericError::new(format!(
+                "Conflicting perturbations of {} in {}",
+                self.model.get_name(src),
+                self.model.get_name(tgt)
+            ))),
+            _ => {
+                self.regulators.insert((src, tgt), value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Parse a perturbation using the compact syntax (``A%0``, ``A%1:2``, ``A@B%0``)
+    pub fn parse(&mut self, s: &str) -> CanFail<GenericError> {
+        let (lhs, spec) = split_once(s, '%')?;
+        let (min, max) = match spec.split_once(':') {
+            None => (parse_level(s, spec)?, parse_level(s, spec)?),
+            Some((min, max)) => (parse_level(s, min)?, parse_level(s, max)?),
+        };
+
+        match lhs.split_once('@') {
+            None => self.restrict_component(lhs, min, max),
+            Some((src, tgt)) => {
+                if min != max {
+                    return Err(GenericError::new(format!(
+                        "Ranges are not supported for interactions: {}",
+                        s
+                    )));
+                }
+                if src.contains(':') {
+                    if min > 1 {
+                        return Err(invalid_level(src, min));
+                    }
+                    self.lock_regulator(src, tgt, min == 1)
+                } else {
+                    self.fix_regulator(src, tgt, min)
+                }
+            }
+        }
+    }
+
+    /// Parse a list of perturbations to apply together.
+    ///
+    /// Perturbations are separated by commas, spaces or new lines,
+    /// and lines starting with ``#`` are ignored.
+    pub fn parse_list(&mut self, text: &str) -> CanFail<GenericError> {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            for s in line.split(|c: char| c == ',' || c.is_whitespace()) {
+                if !s.is_empty() {
+                    self.parse(s)?;
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Find the variable associated to a component name with an optional threshold suffix
+    fn get_threshold_handle(&self, name: &str) -> Result<usize, GenericError> {
+        let (cpt, threshold) = match name.split_once(':') {
+            None => return self.model.get_handle_res(name),
+            Some((cpt, t)) => (cpt, t),
+        };
+        let cid = self.model.get_handle_res(cpt)?;
+        threshold
+            .parse()
+            .ok()
+            .and_then(|t| self.model.get_variable(cid, t))
+            .ok_or_else(|| GenericError::new(format!("Unknown threshold {}", name)))
+    }
+
+    /// Describe the selected perturbations using the compact syntax, in a stable order
+    fn arguments(&self) -> Vec<String> {
+        let components = self.components.iter().map(|(cid, (min, max))| {
+            let name = self.model.get_name(*cid);
+            if min == max {
+                format!("{}%{}", name, min)
+            } else {
+                format!("{}%{}:{}", name, min, max)
+            }
+        });
+        let regulators = self.regulators.iter().map(|((src, tgt), value)| {
+            let var = self.model.get_component_value(*src).unwrap();
+            format!(
+                "{}:{}@{}%{}",
+                self.model.get_name(var.component),
+                var.value,
+                self.model.get_name(*tgt),
+                *value as usize
+            )
+        });
+        components.chain(regulators).sorted().collect()
+    }
+
     pub fn apply(&mut self) {
+        let started = self.model.start_edit("perturbation");
+        let arguments = self.arguments();
+        self.model.add_modification("perturbation", arguments);
+
         // Classical perturbations
-        for (uid, value) in &self.components {
-            self.model.lock_variable(*uid, *value);
+        for (cid, (min, max)) in &self.components {
+            self.model
+                .restrict_component(*cid, *min, *max)
+                .expect("Ranges are checked when adding perturbations");
         }
 
         // Perturbed interactions
-        // Collect the modified targets and update their rules
-        for t in self
-            .regulators
-            .iter()
-            .map(|((_, t), _)| *t)
-            .collect::<HashSet<usize>>()
-        {
-            self.target = Some(t);
-            unimplemented!("TODO: Use the replacer API to rewrite the rules for {}", t);
+        for ((src, tgt), value) in &self.regulators {
+            self.model.lock_regulator(*src, *tgt, *value);
         }
+
+        self.model.end_edit(started);
+    }
+}
+
+fn split_once(s: &str, sep: char) -> Result<(&str, &str), GenericError> {
+    s.split_once(sep)
+        .map(|(a, b)| (a.trim(), b.trim()))
+        .ok_or_else(|| GenericError::new(format!("Invalid perturbation: {}", s)))
+}
+
+fn parse_level(s: &str, level: &str) -> Result<usize, GenericError> {
+    level
+        .parse()
+        .map_err(|_| GenericError::new(format!("Invalid level in perturbation: {}", s)))
+}
+
+fn invalid_level(name: &str, level: usize) -> GenericError {
+    GenericError::new(format!("Invalid level for {}: {}", name, level))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::expr::Expr;
+    use crate::model::modifier::perturbation::Perturbator;
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    fn model() -> QModel {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        model.ensure_threshold(a, 2).unwrap();
+        model.set_rule_from_str(a, 1, "C").unwrap();
+        model.set_rule_from_str(a, 2, "C & B").unwrap();
+        model.set_rule_from_str(b, 1, "A & !C").unwrap();
+        model.set_rule_from_str(c, 1, "A:2 | B").unwrap();
+        model
+    }
+
+    #[test]
+    fn perturb_interactions() {
+        let mut model = model();
+        let a = model.get_handle("A").unwrap();
+        let a2 = model.get_variable(a, 2).unwrap();
+        let b = model.get_handle("B").unwrap();
+        let c = model.get_handle("C").unwrap();
+
+        let mut perturbator = Perturbator::new(&mut model);
+        perturbator.guess_lock("A@B", false).unwrap();
+        perturbator.guess_lock("A:2@C", true).unwrap();
+        assert!(perturbator.guess_lock("A:3@C", true).is_err());
+        assert!(perturbator.guess_lock("A@D", true).is_err());
+        perturbator.apply();
+
+        // The regulator is only locked in the selected target
+        assert_eq!(model.get_var_rule(b), Expr::FALSE);
+        assert_eq!(model.get_var_rule(c), Expr::TRUE);
+        assert!(model.get_var_rule(a2).get_literals().is_fixed_at(b, true));
+    }
+
+    #[test]
+    fn perturbation_syntax() {
+        let mut model = model();
+        let a = model.get_handle("A").unwrap();
+        let a2 = model.get_variable(a, 2).unwrap();
+        let b = model.get_handle("B").unwrap();
+        let c = model.get_handle("C").unwrap();
+
+        let mut perturbator = Perturbator::new(&mut model);
+        perturbator
+            .parse_list("# Combined perturbation\nA%1:2, B@C%1\nA@B%1")
+            .unwrap();
+        assert!(perturbator.parse("A%3").is_err());
+        assert!(perturbator.parse("A%2:1").is_err());
+        assert!(perturbator.parse("A@B%0:1").is_err());
+        assert!(perturbator.parse("A").is_err());
+        assert!(perturbator.parse_range("A=1..x").is_err());
+        assert!(perturbator.parse_range("A=1.2").is_err());
+        assert!(perturbator.parse("A%0").is_err());
+        assert!(perturbator.parse("A@B%0").is_err());
+        perturbator.parse("A%1:2").unwrap();
+        perturbator.apply();
+
+        assert_eq!(model.get_var_rule(a), Expr::TRUE);
+        assert_eq!(model.get_var_rule(c), Expr::TRUE);
+        assert_eq!(model.get_var_rule(b), Expr::NATOM(c));
+
+        // Fix a multivalued component
+        let mut perturbator = Perturbator::new(&mut model);
+        perturbator.parse_fixed("A=2").unwrap();
+        perturbator.apply();
+        assert_eq!(model.get_var_rule(a), Expr::TRUE);
+        assert_eq!(model.get_var_rule(a2), Expr::ATOM(a));
+
+        let mut perturbator = Perturbator::new(&mut model);
+        perturbator.parse_range("A=0..1").unwrap();
+        perturbator.apply();
+        assert_eq!(model.get_var_rule(a2), Expr::FALSE);
+
+        // Knock-out of the first threshold
+        model.lock_variable(a, false);
+        let rules = model.rules.get(a).unwrap();
+        assert_eq!(rules.raw_variable_formula(1), Expr::FALSE);
+        assert_eq!(rules.raw_variable_formula(2), Expr::FALSE);
     }
 }
@@ -0,0 +1,361 @@
+//! Reduce models by eliminating components.
+//!
+//! A component which does not regulate itself can be removed by substituting its function
+//! into the rules of its targets. This preserves the fixed points of the model, and the
+//! value of eliminated components in a fixed point is given by their function.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::func::expr::{AtomReplacer, Expr};
+use crate::func::pattern::Pattern;
+use crate::func::state::State;
+use crate::func::BoolRepr;
+use crate::helper::error::{CanFail, GenericError};
+use crate::model::actions::fixpoints::FixedPoints;
+use crate::model::{GroupedVariables, QModel};
+use crate::variables::ModelVariables;
+
+/// Record of the variables eliminated from a model, to map results back to the full model
+#[derive(Clone)]
+pub struct Reduction {
+    original: Rc<ModelVariables>,
+    removed: Vec<(usize, Expr)>,
+}
+
+/// Select and eliminate components from a model
+pub struct Reducer<'a> {
+    model: &'a mut QModel,
+    selected: Vec<usize>,
+    skipped: Vec<usize>,
+    outputs: bool,
+    fixed: bool,
+}
+
+/// Replace eliminated variables with their function
+struct Substitution<'a> {
+    functions: &'a HashMap<usize, Expr>,
+}
+
+impl AtomReplacer for Substitution<'_> {
+    fn replace(&mut self, var: usize, value: bool) -> Option<Expr> {
+        self.functions
+            .get(&var)
+            .map(|e| if value { e.clone() } else { e.not() })
+    }
+}
+
+impl Reduction {
+    /// The variables of the model before the first elimination
+    pub fn original_variables(&self) -> Rc<ModelVariables> {
+        Rc::clone(&self.original)
+    }
+
+    /// The eliminated variables and their functions, in order of elimination
+    pub fn removed(&self) -> &[(usize, Expr)] {
+        &self.removed
+    }
+
+    /// Complete a fixed point of the reduced model with the values of eliminated variables.
+    ///
+    /// The functions of the eliminated variables only depend on variables which were
+    /// eliminated later or kept in the model: they are evaluated in reverse order.
+    pub fn expand(&self, p: &Pattern) -> Pattern {
+        let mut state: State = p.positive().clone();
+        let mut result = p.clone();
+        for (vid, e) in self.removed.iter().rev() {
+            let value = e.eval(&state);
+            if value {
+                state.insert(*vid);
+            }
+            result.set(*vid, value);
+        }
+        result
+    }
+
+    /// Map fixed points of the reduced model back to the full model
+    pub fn expand_fixed(&self, fixed: &FixedPoints) -> FixedPoints {
+        let patterns = fixed.patterns().iter().map(|p| self.expand(p)).collect();
+        FixedPoints::new(self.original_variables(), patterns)
+    }
+}
+
+impl<'a> Reducer<'a> {
+    pub fn new(model: &'a mut QModel) -> Self {
+        Reducer {
+            model,
+            selected: vec![],
+            skipped: vec![],
+            outputs: false,
+            fixed: false,
+        }
+    }
+
+    /// Select a component to eliminate
+    pub fn remove(&mut self, name: &str) -> CanFail<GenericError> {
+        let cid = self.model.get_handle_res(name)?;
+        self.selected.push(cid);
+        Ok(())
+    }
+
+    /// Eliminate components which do not regulate any other component
+    pub fn set_outputs(&mut self, b: bool) -> &mut Self {
+        self.outputs = b;
+        self
+    }
+
+    /// Eliminate components with constant functions and propagate their values.
+    ///
+    /// Components without any rule are fixed at level 0, as in the rest of the model.
+    pub fn set_fixed(&mut self, b: bool) -> &mut Self {
+        self.fixed = b;
+        self
+    }
+
+    /// Eliminate the selected components, constants and outputs, and record the reduction.
+    ///
+    /// Returns the number of eliminated components. Self-regulated components can
+    /// not be eliminated, selecting one leaves the model unchanged. Selected components
+    /// which become self-regulated after the elimination of previous ones are skipped.
+    pub fn apply(&mut self) -> Result<usize, GenericError> {
+        for cid in self.selected.iter() {
+            if is_self_regulated(self.model, *cid) {
+                return Err(GenericError::new(format!(
+                    "Can not eliminate the self-regulated component {}",
+                    self.model.get_name(*cid)
+                )));
+            }
+        }
+
+        let started = self.model.start_edit("reduction");
+        let mut arguments: Vec<String> = self
+            .selected
+            .iter()
+            .map(|cid| self.model.get_name(*cid).to_owned())
+            .collect();
+        if self.outputs {
+            arguments.push("--outputs".to_owned());
+        }
+        if self.fixed {
+            arguments.push("--fixed".to_owned());
+        }
+        let mut reduction = match self.model.reduction() {
+            Some(r) => Reduction::clone(&r),
+            None => Reduction {
+                original: self.model.frozen_variables(),
+                removed: vec![],
+            },
+        };
+        let mut count = 0;
+        for cid in self.selected.iter() {
+            // The selection may have been eliminated already if it was listed twice
+            if self.model.get_variables(*cid).is_empty() {
+                continue;
+            }
+            if is_self_regulated(self.model, *cid) {
+                self.skipped.push(*cid);
+                continue;
+            }
+            eliminate(self.model, *cid, &mut reduction);
+            count += 1;
+        }
+
+        loop {
+            let mut candidates = vec![];
+            if self.fixed {
+                candidates.extend(
+                    self.model
+                        .components()
+                        .filter(|c| is_constant(self.model, **c)),
+                );
+            }
+            if self.outputs && candidates.is_empty() {
+                candidates.extend(find_outputs(self.model));
+            }
+            if candidates.is_empty() {
+                break;
+            }
+            for cid in candidates {
+                eliminate(self.model, cid, &mut reduction);
+                count += 1;
+            }
+        }
+
+        // Edits which did not eliminate anything are discarded
+        if count > 0 {
+            self.model.add_modification("reduce", arguments);
+            self.model.set_reduction(reduction);
+        }
+        self.model.end_edit(started);
+        Ok(count)
+    }
+
+    /// Names of the selected components which were skipped as they became self-regulated
+    pub fn skipped(&self) -> Vec<&str> {
+        self.skipped
+            .iter()
+            .map(|cid| self.model.get_name(*cid))
+            .collect()
+    }
+}
+
+/// The function of each variable of a component, ignoring its own variables
+fn component_functions(model: &QModel, cid: usize) -> Vec<(usize, Expr)> {
+    let rules = model.rules.get(cid);
+    model
+        .get_variables(cid)
+        .iter()
+        .enumerate()
+        .map(|(i, vid)| {
+            let e = match rules {
+                None => Expr::FALSE,
+                Some(r) => r.raw_variable_formula(i + 1),
+            };
+            (*vid, e)
+        })
+        .collect()
+}
+
+fn is_self_regulated(model: &QModel, cid: usize) -> bool {
+    let variables = model.get_variables(cid);
+    component_functions(model, cid).iter().any(|(_, e)| {
+        let literals = e.get_literals();
+        variables
+            .iter()
+            .any(|v| literals.is_fixed_at(*v, true) || literals.is_fixed_at(*v, false))
+    })
+}
+
+fn is_constant(model: &QModel, cid: usize) -> bool {
+    component_functions(model, cid)
+        .iter()
+        .all(|(_, e)| matches!(e, Expr::TRUE | Expr::FALSE))
+}
+
+/// Components which are not used in any rule
+fn find_outputs(model: &QModel) -> Vec<usize> {
+    let regulators: HashSet<usize> = model.interaction_graph().iter().map(|i| i.source).collect();
+    model
+        .components()
+        .filter(|c| !regulators.contains(c))
+        .copied()
+        .collect()
+}
+
+/// Substitute the functions of a component in all rules and remove it
+fn eliminate(model: &mut QModel, cid: usize, reduction: &mut Reduction) {
+    let functions = component_functions(model, cid);
+    let map: HashMap<usize, Expr> = functions.iter().cloned().collect();
+    let mut replacer = Substitution { functions: &map };
+
+    let components: Vec<usize> = model.components().copied().collect();
+    for target in components {
+        if target == cid {
+            continue;
+        }
+        let mut rule = match model.rules.get(target) {
+            None => continue,
+            Some(r) => r.clone(),
+        };
+        let mut changed = false;
+        rule.map_assignments(|assign| {
+            let expr: Rc<Expr> = assign.formula.convert_as();
+            if let Some(e) = expr.replace_variables(&mut replacer) {
+                assign.formula.set(e.simplify().unwrap_or(e));
+                changed = true;
+            }
+        });
+        if changed {
+            Rc::make_mut(&mut model.rules).replace(target, rule);
+        }
+    }
+
+    model.discard_component(cid);
+    reduction.removed.extend(functions);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::func::pattern::Pattern;
+    use crate::model::modifier::reduction::Reducer;
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    fn model() -> QModel {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        let d = model.ensure("D");
+        let e = model.ensure("E");
+        model.set_rule_from_str(a, 1, "A | C").unwrap();
+        model.set_rule_from_str(b, 1, "A & !E").unwrap();
+        model.set_rule_from_str(c, 1, "B").unwrap();
+        model.set_rule_from_str(d, 1, "C").unwrap();
+        model.set_rule_from_str(e, 1, "false").unwrap();
+        model
+    }
+
+    #[test]
+    fn eliminate_components() {
+        let mut model = model();
+        let a = model.get_handle("A").unwrap();
+        let b = model.get_handle("B").unwrap();
+        let c = model.get_handle("C").unwrap();
+        let d = model.get_handle("D").unwrap();
+
+        // Self-regulated components are refused
+        let mut reducer = Reducer::new(&mut model);
+        reducer.remove("A").unwrap();
+        assert!(reducer.apply().is_err());
+        assert!(Reducer::new(&mut model).remove("X").is_err());
+
+        let mut reducer = Reducer::new(&mut model);
+        reducer.remove("C").unwrap();
+        reducer.set_outputs(true).set_fixed(true);
+        assert_eq!(reducer.apply().unwrap(), 3);
+        assert_eq!(model.components().len(), 2);
+        assert_eq!(format!("{}", model.get_var_rule(a)), "(v0 | v1)");
+
+        // Map a fixed point back to the full model
+        let reduction = model.reduction().unwrap();
+        let mut p = Pattern::new();
+        p.set(a, true);
+        p.set(b, true);
+        let full = reduction.expand(&p);
+        assert!(full.is_fixed_at(c, true));
+        assert!(full.is_fixed_at(d, true));
+        assert_eq!(full.len(), 5);
+        assert_eq!(reduction.original_variables().components().len(), 5);
+        assert_eq!(model.provenance().lineage().len(), 1);
+
+        // Reductions which eliminate nothing are not recorded
+        let edits = model.history().done().count();
+        let mut reducer = Reducer::new(&mut model);
+        reducer.set_fixed(true);
+        assert_eq!(reducer.apply().unwrap(), 0);
+        assert_eq!(model.provenance().lineage().len(), 1);
+        assert_eq!(model.history().done().count(), edits);
+    }
+
+    #[test]
+    fn skip_new_self_regulation() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        model.set_rule_from_str(a, 1, "B").unwrap();
+        model.set_rule_from_str(b, 1, "C").unwrap();
+        model.set_rule_from_str(c, 1, "B").unwrap();
+
+        // Eliminating B turns C into a self-regulated component
+        let mut reducer = Reducer::new(&mut model);
+        reducer.remove("B").unwrap();
+        reducer.remove("C").unwrap();
+        assert_eq!(reducer.apply().unwrap(), 1);
+        assert_eq!(reducer.skipped(), vec!["C"]);
+        assert_eq!(model.components().len(), 2);
+        assert_eq!(format!("{}", model.get_var_rule(c)), "v2");
+        assert_eq!(format!("{}", model.get_var_rule(a)), "v2");
+    }
+}
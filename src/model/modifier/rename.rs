@@ -0,0 +1,212 @@
+//! Rename several components at once.
+//!
+//! New names are given explicitly, loaded from a mapping with two columns, or derived from
+//! the current names using a regex substitution (``s/_human$//``). All new names are checked
+//! before renaming: collisions are reported together and leave the model unchanged.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::helper::error::{CanFail, GenericError};
+use crate::model::QModel;
+use crate::variables::GroupedVariables;
+
+/// Collect new names for a group of components
+pub struct Renamer<'a> {
+    model: &'a mut QModel,
+    names: HashMap<usize, String>,
+}
+
+impl<'a> Renamer<'a> {
+    pub fn new(model: &'a mut QModel) -> Self {
+        Renamer {
+            model,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Select a new name for a component
+    pub fn rename(&mut self, source: &str, target: &str) -> CanFail<GenericError> {
+        let cid = self.model.get_handle_res(source)?;
+        if self.model.get_component_value(cid).map(|v| v.value) != Some(1) {
+            return Err(GenericError::new(format!(
+                "Thresholds can not be renamed: {}",
+                source
+            )));
+        }
+        if let Some(other) = self.names.insert(cid, target.to_owned()) {
+            if other != target {
+                return Err(GenericError::new(format!(
+                    "{} renamed to both {} and {}",
+                    source, other, target
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Load a mapping with two columns: the current and new names.
+    ///
+    /// Columns are separated by spaces, tabulations or a comma. Empty lines and lines
+    /// starting with ``#`` are ignored.
+    pub fn parse_mapping(&mut self, text: &str) -> CanFail<GenericError> {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let columns: Vec<&str> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .collect();
+            match columns.as_slice() {
+                [source, target] => self.rename(source, target)?,
+                _ => {
+                    return Err(GenericError::new(format!(
+                        "Expected two names in mapping line: {}",
+                        line
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rename all components matching a regex, returns the number of matching components.
+    ///
+    /// The replacement can refer to capture groups (``$1``).
+    pub fn substitute(&mut self, pattern: &str, replacement: &str) -> Result<usize, GenericError> {
+        let re = Regex::new(pattern)
+            .map_err(|e| GenericError::new(format!("Invalid regular expression: {}", e)))?;
+        let renamed: Vec<(String, String)> = self
+            .model
+            .components()
+            .map(|cid| self.model.get_name(*cid))
+            .filter(|name| re.is_match(name))
+            .map(|name| (name.to_owned(), re.replace(name, replacement).into_owned()))
+            .collect();
+        for (source, target) in renamed.iter() {
+            self.rename(source, target)?;
+        }
+        Ok(renamed.len())
+    }
+
+    /// Parse a substitution in the sed syntax: ``s/pattern/replacement/``.
+    ///
+    /// The character following the initial ``s`` is used as delimiter. As in sed, it can be
+    /// used in the pattern or replacement if it is escaped with a backslash (``s/a\/b/c/``).
+    pub fn parse_substitution(&mut self, s: &str) -> Result<usize, GenericError> {
+        let invalid = || GenericError::new(format!("Invalid substitution: {}", s));
+        let mut chars = s.trim().chars();
+        if chars.next() != Some('s') {
+            return Err(invalid());
+        }
+        let delimiter = chars.next().ok_or_else(invalid)?;
+        let fields = split_escaped(chars.as_str(), delimiter);
+        match fields.as_slice() {
+            [pattern, replacement, last] if last.is_empty() => {
+                self.substitute(pattern, replacement)
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Rename the selected components, returns the number of renamed components
+    pub fn apply(&mut self) -> Result<usize, GenericError> {
+        let mut names: Vec<(usize, String)> = self.names.drain().collect();
+        names.sort_unstable();
+        self.model.set_names(&names)
+    }
+}
+
+/// Split a string on a delimiter which is not escaped, and unescape the delimiter.
+///
+/// Other escaped characters are kept with their backslash for the regular expression.
+fn split_escaped(s: &str, delimiter: char) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            '\\' => match chars.next() {
+                Some(n) if n == delimiter => field.push(n),
+                Some(n) => {
+                    field.push(c);
+                    field.push(n);
+                }
+                None => field.push(c),
+            },
+            c if c == delimiter => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::modifier::rename::{split_escaped, Renamer};
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn rename_components() {
+        let mut model = QModel::default();
+        let a = model.ensure("A_human");
+        let b = model.ensure("B_human");
+        let c = model.ensure("C");
+        model.set_rule_from_str(a, 2, "B_human & C").unwrap();
+        let a2 = model.get_variable(a, 2).unwrap();
+
+        let mut renamer = Renamer::new(&mut model);
+        assert_eq!(renamer.parse_substitution("s/_human$//").unwrap(), 2);
+        assert_eq!(renamer.apply().unwrap(), 2);
+        assert_eq!(model.get_name(a), "A");
+        assert_eq!(model.get_name(a2), "A:2");
+        assert_eq!(model.get_handle("A:2"), Some(a2));
+        assert!(model.get_handle("A_human").is_none());
+        assert_eq!(format!("{}", model), "A:2 <- (B & C)\n");
+
+        // Exchange names from a mapping
+        let mut renamer = Renamer::new(&mut model);
+        renamer.parse_mapping("# swap\nA, B\n\nB\tA\n").unwrap();
+        renamer.apply().unwrap();
+        assert_eq!(model.get_name(a), "B");
+        assert_eq!(model.get_name(b), "A");
+        assert_eq!(model.get_name(a2), "B:2");
+
+        // Collisions are reported without renaming anything
+        let mut renamer = Renamer::new(&mut model);
+        renamer.rename("A", "X").unwrap();
+        renamer.rename("B", "C").unwrap();
+        assert!(renamer.apply().is_err());
+        assert_eq!(model.get_name(b), "A");
+        assert_eq!(model.get_name(c), "C");
+        assert!(model.undo().is_some());
+        assert_eq!(model.get_name(a), "A");
+
+        let mut renamer = Renamer::new(&mut model);
+        assert!(renamer.rename("A", "B").is_ok());
+        assert!(renamer.rename("A", "C").is_err());
+        assert!(renamer.rename("A:2", "C").is_err());
+        assert!(renamer.parse_mapping("A B C").is_err());
+        assert!(renamer.parse_substitution("s/A/B").is_err());
+        assert!(renamer.parse_substitution("s/(/B/").is_err());
+    }
+
+    #[test]
+    fn escaped_delimiters() {
+        assert_eq!(split_escaped(r"a\/b/c/", '/'), vec!["a/b", "c", ""]);
+        assert_eq!(split_escaped(r"\d\\/x/", '/'), vec![r"\d\\", "x", ""]);
+        assert_eq!(split_escaped(r"a\_b_c_", '_'), vec!["a_b", "c", ""]);
+
+        let mut model = QModel::default();
+        let a = model.ensure("A_human");
+        let mut renamer = Renamer::new(&mut model);
+        assert_eq!(renamer.parse_substitution(r"s/a\/b/c/").unwrap(), 0);
+        assert_eq!(renamer.parse_substitution(r"s_\_human$_\_h_").unwrap(), 1);
+        renamer.apply().unwrap();
+        assert_eq!(model.get_name(a), "A_h");
+    }
+}
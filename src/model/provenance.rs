@@ -0,0 +1,278 @@
+//! Trace the origin of a model and the modifiers applied to it.
+//!
+//! Each model has an origin, created when it is loaded or built from scratch, which provides an
+//! identifier, the source file and format, and a hash of the original content. Identifiers are
+//! unique within a session and do not depend on the time, so the same commands give the same
+//! output. Clones of a
+//! model share the same origin and are numbered in order of creation. The lineage lists the
+//! modifiers applied to the model with their arguments: together with the origin, it identifies
+//! the exact variant of the model used to obtain a result.
+
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json::json;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Identity of the original model, shared by all its clones
+#[derive(Debug)]
+pub struct Origin {
+    id: String,
+    source: Option<String>,
+    format: Option<String>,
+    hash: Option<String>,
+    clones: Cell<usize>,
+}
+
+/// A modifier applied to a model, with its arguments
+#[derive(Clone, Debug, PartialEq)]
+pub struct Modification {
+    pub modifier: String,
+    pub arguments: Vec<String>,
+}
+
+/// Origin and lineage of a model variant
+#[derive(Clone, Debug)]
+pub struct Provenance {
+    origin: Rc<Origin>,
+    clone: usize,
+    lineage: Vec<Modification>,
+}
+
+impl Origin {
+    fn new(source: Option<&str>, format: Option<&str>, content: Option<&str>) -> Self {
+        let hash = content.map(|c| format!("{:016x}", fnv1a(c.as_bytes())));
+
+        // Combine a process-wide counter with the source and content to obtain an identifier
+        // which is unique in a session and reproducible across sessions
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let seed = format!(
+            "{}:{}:{}",
+            count,
+            source.unwrap_or(""),
+            hash.as_deref().unwrap_or("")
+        );
+
+        Origin {
+            id: format!("{:016x}", fnv1a(seed.as_bytes())),
+            source: source.map(str::to_owned),
+            format: format.map(str::to_owned),
+            hash,
+            clones: Cell::new(0),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn format(&self) -> Option<&str> {
+        self.format.as_deref()
+    }
+
+    /// FNV-1a hash of the original content, if the model was parsed
+    pub fn hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
+
+    fn next_clone(&self) -> usize {
+        let next = self.clones.get() + 1;
+        self.clones.set(next);
+        next
+    }
+}
+
+impl Default for Provenance {
+    fn default() -> Self {
+        Provenance {
+            origin: Rc::new(Origin::new(None, None, None)),
+            clone: 0,
+            lineage: vec![],
+        }
+    }
+}
+
+impl Provenance {
+    /// Provenance of a model parsed from a string, with an optional source file and format
+    pub fn parsed(source: Option<&str>, format: Option<&str>, content: &str) -> Self {
+        Provenance {
+            origin: Rc::new(Origin::new(source, format, Some(content))),
+            clone: 0,
+            lineage: vec![],
+        }
+    }
+
+    pub fn origin(&self) -> &Origin {
+        &self.origin
+    }
+
+    /// The clone number: 0 for the original model, then in order of creation
+    pub fn clone_number(&self) -> usize {
+        self.clone
+    }
+
+    pub fn lineage(&self) -> &[Modification] {
+        &self.lineage
+    }
+
+    /// Record a modifier applied to the model
+    pub fn push(&mut self, modifier: &str, arguments: Vec<String>) {
+        self.lineage.push(Modification {
+            modifier: modifier.to_owned(),
+            arguments,
+        });
+    }
+
+    /// Provenance of a new clone, sharing the same origin and lineage
+    pub fn derive(&self) -> Self {
+        Provenance {
+            origin: Rc::clone(&self.origin),
+            clone: self.origin.next_clone(),
+            lineage: self.lineage.clone(),
+        }
+    }
+
+    /// Check if two models have the same origin
+    pub fn same_origin(&self, other: &Provenance) -> bool {
+        Rc::ptr_eq(&self.origin, &other.origin)
+    }
+
+    /// Short identifier of the model variant: origin, clone number and number of modifications
+    pub fn variant(&self) -> String {
+        format!("{}.{}.{}", self.origin.id, self.clone, self.lineage.len())
+    }
+
+    /// Describe the provenance as a JSON object
+    pub fn to_json(&self) -> String {
+        let lineage: Vec<_> = self
+            .lineage
+            .iter()
+            .map(|m| json!({"modifier": m.modifier, "arguments": m.arguments}))
+            .collect();
+        json!({
+            "id": self.origin.id,
+            "source": self.origin.source(),
+            "format": self.origin.format(),
+            "hash": self.origin.hash(),
+            "clone": self.clone,
+            "lineage": lineage,
+        })
+        .to_string()
+    }
+}
+
+impl fmt::Display for Modification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.modifier)?;
+        for arg in &self.arguments {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "model: {}", self.variant())?;
+        if let Some(source) = self.origin.source() {
+            writeln!(f, "source: {}", source)?;
+        }
+        if let Some(format) = self.origin.format() {
+            writeln!(f, "format: {}", format)?;
+        }
+        if let Some(hash) = self.origin.hash() {
+            writeln!(f, "hash: {}", hash)?;
+        }
+        for m in &self.lineage {
+            writeln!(f, "modifier: {}", m)?;
+        }
+        Ok(())
+    }
+}
+
+/// 64 bits FNV-1a hash, stable across platforms and versions
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::provenance::{fnv1a, Provenance};
+    use crate::model::QModel;
+    use crate::variables::GroupedVariables;
+
+    #[test]
+    fn provenance() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+
+        let mut base = Provenance::parsed(Some("model.bnet"), Some("bnet"), "A, B\n");
+        let other = Provenance::parsed(None, None, "A, B\n");
+        assert_eq!(base.origin().hash(), other.origin().hash());
+        assert_ne!(base.origin().id(), other.origin().id());
+        assert!(!base.same_origin(&other));
+
+        base.push("perturbation", vec!["A%0".to_owned()]);
+        let first = base.derive();
+        let second = base.derive();
+        assert!(first.same_origin(&base) && second.same_origin(&base));
+        assert_eq!(first.clone_number(), 1);
+        assert_eq!(second.clone_number(), 2);
+        assert_eq!(second.lineage(), base.lineage());
+
+        let json = base.to_json();
+        assert!(json.contains("\"source\":\"model.bnet\""));
+        assert!(
+            json.contains("\"lineage\":[{\"arguments\":[\"A%0\"],\"modifier\":\"perturbation\"}]")
+        );
+        assert!(other.to_json().contains("\"source\":null"));
+
+        // Escaping is handled by the JSON serializer
+        let mut quoted = Provenance::default();
+        quoted.push("rename", vec!["s/\"a\\b/c/".to_owned()]);
+        assert!(quoted.to_json().contains("[\"s/\\\"a\\\\b/c/\"]"));
+    }
+
+    #[test]
+    fn structural_lineage() {
+        let mut model = QModel::default();
+        let a = model.ensure("A");
+        let b = model.ensure("B");
+        let c = model.ensure("C");
+        model.set_rule_from_str(a, 1, "B").unwrap();
+        model.ensure_threshold(c, 2).unwrap();
+
+        model.sort_components_by_name();
+        model.reorder_components(&[c, b, a]).unwrap();
+        model.sort_components_by_name();
+        model.rename("B", "X").unwrap();
+        model.remove_threshold(c, 2).unwrap();
+        model.remove_component(c);
+
+        let lineage: Vec<String> = model
+            .provenance()
+            .lineage()
+            .iter()
+            .map(|m| format!("{} {}", m.modifier, m.arguments.join(",")))
+            .collect();
+        assert_eq!(
+            lineage,
+            vec![
+                "reorder C,B,A",
+                "sort name",
+                "rename B,X",
+                "remove_threshold C:2",
+                "remove C"
+            ]
+        );
+    }
+}
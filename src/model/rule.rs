@@ -1,7 +1,6 @@
 use crate::func::expr::Expr;
 use crate::func::{Formula, FromBoolRepr};
 use crate::helper::version::Version;
-use crate::variables;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
@@ -24,10 +23,12 @@ pub struct ComponentRules {
 #[derive(Default, Clone)]
 pub struct Rules {
     rules: HashMap<usize, ComponentRules>,
+    #[allow(dead_code)]
     cache: RefCell<ModelCache>,
     version: Version,
 }
 
+#[allow(dead_code)]
 #[derive(Default, Clone)]
 struct ModelCache {
     target_rules: Option<Rc<HashMap<usize, Formula>>>,
@@ -50,6 +51,12 @@ impl Rules {
         self.rules.insert(cid, rule)
     }
 
+    /// Remove all rules for the specified component, and return them if available
+    pub fn remove(&mut self, cid: usize) -> Option<ComponentRules> {
+        self.version.change();
+        self.rules.remove(&cid)
+    }
+
     /// Retrieve the set of rules for a component if it exists
     pub fn get(&self, cid: usize) -> Option<&ComponentRules> {
         self.rules.get(&cid)
@@ -64,15 +71,37 @@ impl Rules {
         self.ensure(cid).push(value, rule);
     }
 
-    /// Restrict the activity of a component
+    /// Replace the Boolean condition for a specific threshold
+    pub fn set(&mut self, cid: usize, value: usize, rule: Formula) {
+        self.ensure(cid).set(value, rule);
+    }
+
+    /// Restrict the activity of a component.
+    ///
+    /// The levels are not checked here: the model validates them against the thresholds.
     pub fn restrict_component(&mut self, cid: usize, min: usize, max: usize) {
         self.ensure(cid).restrict(min, max);
     }
 
-    /// Enforce the activity of a specific variable
+    /// Enforce the level of a component, which should be at most its maximal level
     pub fn lock_component(&mut self, cid: usize, value: usize) {
         self.ensure(cid).lock(value);
     }
+
+    /// Merge two adjacent levels of a component: assignments to higher levels are shifted down.
+    ///
+    /// The first threshold can not be removed (value > 1), so no assignment is shifted to level 0.
+    pub fn remove_threshold(&mut self, cid: usize, value: usize) {
+        debug_assert!(value > 1);
+        if let Some(rules) = self.rules.get_mut(&cid) {
+            rules.map_assignments(|a| {
+                if a.target >= value {
+                    a.target -= 1;
+                }
+            });
+            self.version.change();
+        }
+    }
 }
 
 impl ComponentRules {
@@ -82,11 +111,23 @@ impl ComponentRules {
         }
     }
 
-    pub fn assignments(&self) -> Iter<Assign>{
+    /// Build rules from the conditions required to reach each threshold, in increasing order.
+    ///
+    /// The conditions of successive thresholds should be nested, i.e. the condition of a
+    /// threshold should imply the conditions of all lower thresholds.
+    pub fn from_thresholds(conditions: impl IntoIterator<Item = Expr>) -> Self {
+        let mut rules = ComponentRules::new();
+        for (idx, e) in conditions.into_iter().enumerate() {
+            rules.push(idx + 1, Formula::from(e));
+        }
+        rules
+    }
+
+    pub fn assignments(&self) -> Iter<'_, Assign> {
         self.assignments.iter()
     }
 
-    pub fn map_assignments<F: FnMut(&mut Assign) -> ()>(&mut self, f: F) {
+    pub fn map_assignments<F: FnMut(&mut Assign)>(&mut self, f: F) {
         self.assignments.iter_mut().for_each(f)
     }
 
@@ -95,7 +136,6 @@ impl ComponentRules {
     }
 
     fn lock(&mut self, value: usize) {
-        let value = variables::check_tval(value);
         self.clear();
         if value > 0 {
             self.push(value, Formula::from_bool(true));
@@ -103,8 +143,6 @@ impl ComponentRules {
     }
 
     fn restrict(&mut self, min: usize, max: usize) {
-        let min = variables::check_tval(min);
-        let max = variables::check_tval(max);
         if max <= min {
             self.lock(min);
             return;
@@ -142,6 +180,23 @@ impl ComponentRules {
         })
     }
 
+    /// Replace all assignments to the same target value with a new one.
+    ///
+    /// The new assignment takes the place of the first existing one to preserve the priorities.
+    pub fn set(&mut self, value: usize, condition: Formula) {
+        match self.assignments.iter().position(|a| a.target == value) {
+            None => self.push(value, condition),
+            Some(idx) => {
+                self.assignments[idx].formula = condition;
+                let mut cur = 0;
+                self.assignments.retain(|a| {
+                    cur += 1;
+                    cur <= idx + 1 || a.target != value
+                });
+            }
+        }
+    }
+
     pub fn insert(&mut self, value: usize, condition: Formula) {
         self.assignments.insert(
             0,
@@ -164,6 +219,7 @@ impl Assign {
     }
 }
 
+#[allow(dead_code)]
 impl ModelCache {
     fn clear(&mut self) {
         self.target_rules = None;
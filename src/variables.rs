@@ -1,25 +1,84 @@
 //! Manage a collection of named components and associated Boolean variables
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 use regex::Regex;
 
 use crate::func::*;
-use crate::helper::error::GenericError;
+use crate::helper::error::{CanFail, GenericError};
 use crate::helper::version::{Version, Versionned};
 use once_cell::sync::Lazy;
 use std::slice::Iter;
+use std::str::FromStr;
+
+/// Valid identifiers, as accepted by the parsers and produced by the normalization of names
+static RE_UID: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*$").unwrap());
+static RE_THRESHOLD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?P<cpt>.*):(?P<th>[0-9]+)$").unwrap());
+/// Check if a string can be used as the name of a component
+pub fn is_valid_name(name: &str) -> bool {
+    RE_UID.is_match(name)
+}
+
+/// Parse the assignment of a level to a component (``A=2``), returns the component and the level.
+///
+/// Fails if the component does not exist or if the level is above its maximal level.
+pub fn parse_level_assignment(
+    grp: &impl GroupedVariables,
+    s: &str,
+) -> Result<(usize, usize), GenericError> {
+    let (name, level) = s
+        .split_once('=')
+        .map(|(name, level)| (name.trim(), level.trim()))
+        .ok_or_else(|| GenericError::new(format!("Expected NAME=LEVEL: {}", s)))?;
+    let cid = grp.get_handle_res(name)?;
+    if grp.get_component_value(cid).map(|v| v.value) != Some(1) {
+        return Err(GenericError::new(format!("{} is not a component", name)));
+    }
+    match level.parse() {
+        Ok(level) if level <= grp.get_variables(cid).len() => Ok((cid, level)),
+        _ => Err(GenericError::new(format!(
+            "Invalid level for {}: {}",
+            name, level
+        ))),
+    }
+}
 
-/// Maximal number of variables associated to each component
-pub static MAXVAL: usize = 9;
+/// Highest level of a component.
+///
+/// Each level above 0 is associated to a Boolean variable which is created when the level is
+/// used: this limit prevents a typo in a model or a command from creating millions of variables.
+pub const MAX_LEVEL: usize = 999;
 
-static RE_UID: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z][a-zA-Z01-9_]*$").unwrap());
-static RE_VAR_ID: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^(?P<cpt>[a-zA-Z_][a-zA-Z01-9_]*)(:(?P<th>[1-9]))?$").unwrap());
 static EMPTY_USIZE_VEC: Vec<usize> = vec![];
 static EMPTY_NAME: Lazy<String> = Lazy::new(|| String::from(""));
 static DEFAULT_NAME_PATTERN: Lazy<String> = Lazy::new(|| String::from("cpt"));
 
+/// Normalisation of component names which can not be used as identifiers
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum NamePolicy {
+    /// Replace invalid characters with underscores
+    #[default]
+    REPLACE,
+    /// Remove invalid characters
+    STRIP,
+    /// Use generic names (cpt, cpt_1, ...)
+    GENERIC,
+}
+
+impl FromStr for NamePolicy {
+    type Err = GenericError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "replace" => Ok(NamePolicy::REPLACE),
+            "strip" => Ok(NamePolicy::STRIP),
+            "generic" => Ok(NamePolicy::GENERIC),
+            _ => Err(GenericError::new(format!("Unknown naming policy: {}", s))),
+        }
+    }
+}
+
 /// Maintain a list of components and associated variables.
 ///
 /// Each component is associated to one or several variables with ordered thresholds.
@@ -41,6 +100,11 @@ pub struct ModelVariables {
     var_to_cpt_value: HashMap<usize, Variable>,
 
     names: HashMap<usize, String>,
+
+    // Original names of components created with a normalized name
+    policy: NamePolicy,
+    labels: HashMap<usize, String>,
+    original2uid: HashMap<String, usize>,
 }
 
 /// A Boolean variable associated to a qualitative threshold of one of the components
@@ -58,6 +122,8 @@ pub enum GroupedVariableError {
 
 pub trait GroupedVariables {
     /// Find a variable by name if it exists.
+    ///
+    /// Components can also be found by their original name if it was normalized.
     fn get_handle(&self, name: &str) -> Option<usize>;
 
     fn get_component_value(&self, vid: usize) -> Option<&Variable>;
@@ -90,23 +156,32 @@ pub trait GroupedVariables {
         }
     }
 
-    fn components(&self) -> Iter<usize>;
+    fn components(&self) -> Iter<'_, usize>;
 
-    fn variables(&self) -> Iter<usize>;
+    fn variables(&self) -> Iter<'_, usize>;
 
     /// Find or create a component with a given name.
     ///
-    /// If the name is invalid, a normalized name will be used, exact behaviour
-    /// is still undefined:
-    /// * normalize before looking for a match may match a different existing component,
-    /// * normalize and use as pattern: may create multiple components for the same original name,
-    /// * keep track of previous invalid names: can add some noise and corner cases.
-    fn ensure(&mut self, name: &str) -> usize;
+    /// The name can use the threshold suffix (``A:2``) to designate a specific variable.
+    /// If the name is invalid, the new component gets a normalized name and keeps the original
+    /// name as a label. Looking for the same original name later yields the same component.
+    /// Fails without creating anything if the threshold suffix is not between 1 and ``MAX_LEVEL``.
+    fn try_ensure(&mut self, name: &str) -> Result<usize, GenericError>;
+
+    /// Find or create a component with a given name, see ``try_ensure``.
+    ///
+    /// Panics if the name has an invalid threshold suffix: names given by users or read
+    /// from files should use ``try_ensure``.
+    fn ensure(&mut self, name: &str) -> usize {
+        self.try_ensure(name)
+            .expect("Threshold suffixes are checked before calling ensure")
+    }
 
     /// Find or create a variable for an existing component and a specific threshold value.
     ///
     /// Invalid handles trigger the creation of a new component.
-    fn ensure_threshold(&mut self, handle: usize, value: usize) -> usize;
+    /// Fails if the value is not between 1 and ``MAX_LEVEL``.
+    fn ensure_threshold(&mut self, handle: usize, value: usize) -> Result<usize, GenericError>;
 
     /// Change the name of a component.
     ///
@@ -156,9 +231,252 @@ impl ModelVariables {
         self.variable(h).map(|v| v.component)
     }
 
+    /// Select the normalisation of invalid names for new components
+    pub fn set_name_policy(&mut self, policy: NamePolicy) {
+        self.policy = policy;
+    }
+
+    pub fn name_policy(&self) -> NamePolicy {
+        self.policy
+    }
+
+    /// The original name of a component if it was normalized
+    pub fn get_label(&self, cid: usize) -> Option<&str> {
+        self.labels.get(&cid).map(String::as_str)
+    }
+
+    /// Associate an original name to a component, returns false if the component does not exist.
+    ///
+    /// Components created with a normalized name can still be found by the name used to create them.
+    pub fn set_label(&mut self, cid: usize, label: &str) -> bool {
+        if !self.cpt_to_variables.contains_key(&cid) {
+            return false;
+        }
+        self.labels.insert(cid, label.to_owned());
+        self.changed();
+        true
+    }
+
+    /// Find a variable by identifier, ignoring the labels.
+    ///
+    /// Components created with a normalized name are also found by the name used to create them.
+    pub fn find_identifier(&self, name: &str) -> Option<usize> {
+        self.name2uid
+            .get(name)
+            .or_else(|| self.original2uid.get(name))
+            .copied()
+    }
+
+    /// List the labelled components and their original names, in the order of components
+    pub fn labels(&self) -> impl Iterator<Item = (usize, &str)> + '_ {
+        self.components
+            .iter()
+            .filter_map(move |cid| self.get_label(*cid).map(|label| (*cid, label)))
+    }
+
+    /// Valid identifier derived from an invalid name, according to the naming policy
+    fn normalize(&self, name: &str) -> String {
+        let valid = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+        let base: String = match self.policy {
+            NamePolicy::REPLACE => name
+                .chars()
+                .map(|c| if valid(&c) { c } else { '_' })
+                .collect(),
+            NamePolicy::STRIP => name.chars().filter(valid).collect(),
+            NamePolicy::GENERIC => String::new(),
+        };
+        let base = match base.chars().next() {
+            None => DEFAULT_NAME_PATTERN.to_string(),
+            Some(c) if c.is_ascii_digit() => format!("_{}", base),
+            Some(_) => base,
+        };
+        self.find_free_name(&base).unwrap_or(base)
+    }
+
+    /// Remove a component and all associated variables.
+    ///
+    /// The handles of other components and variables are unchanged.
+    /// Returns false if the component does not exist.
+    pub fn remove_component(&mut self, cid: usize) -> bool {
+        let variables = match self.cpt_to_variables.remove(&cid) {
+            None => return false,
+            Some(v) => v,
+        };
+
+        self.components.retain(|c| *c != cid);
+        self.variables.retain(|v| !variables.contains(v));
+        for vid in variables.iter() {
+            self.var_to_cpt_value.remove(vid);
+            if let Some(name) = self.names.remove(vid) {
+                self.name2uid.remove(&name);
+            }
+        }
+        self.labels.remove(&cid);
+        self.original2uid.retain(|_, c| *c != cid);
+        self.changed();
+        true
+    }
+
+    /// Remove a threshold of a component, merging the two adjacent levels.
+    ///
+    /// The following thresholds are shifted down and renamed, their handles are unchanged.
+    /// The first threshold identifies the component and can not be removed.
+    /// Returns false if the threshold does not exist.
+    pub fn remove_threshold(&mut self, cid: usize, value: usize) -> bool {
+        let variables = match self.cpt_to_variables.get_mut(&cid) {
+            None => return false,
+            Some(v) => v,
+        };
+        if value < 2 || value > variables.len() {
+            return false;
+        }
+
+        let vid = variables.remove(value - 1);
+        let shifted = variables[value - 1..].to_vec();
+        self.variables.retain(|v| *v != vid);
+        self.var_to_cpt_value.remove(&vid);
+        if let Some(name) = self.names.remove(&vid) {
+            self.name2uid.remove(&name);
+        }
+
+        let cptname = self.get_name(cid).to_owned();
+        for (idx, v) in shifted.into_iter().enumerate() {
+            let value = value + idx;
+            if let Some(name) = self.names.remove(&v) {
+                self.name2uid.remove(&name);
+            }
+            let name = format!("{}:{}", cptname, value);
+            self.name2uid.insert(name.clone(), v);
+            self.names.insert(v, name);
+            self.var_to_cpt_value.insert(v, Variable::new(cid, value));
+        }
+        self.changed();
+        true
+    }
+
+    /// Change the order of the components.
+    ///
+    /// The new order must contain each existing component exactly once, the variables
+    /// are sorted accordingly. Returns false and leaves the order unchanged otherwise.
+    pub fn reorder_components(&mut self, order: &[usize]) -> bool {
+        if order.len() != self.components.len()
+            || order.iter().collect::<HashSet<_>>().len() != order.len()
+            || order.iter().any(|c| !self.cpt_to_variables.contains_key(c))
+        {
+            return false;
+        }
+
+        self.variables = order
+            .iter()
+            .flat_map(|c| self.cpt_to_variables.get(c).unwrap().iter())
+            .copied()
+            .collect();
+        self.components = order.to_vec();
+        self.changed();
+        true
+    }
+
+    /// Rename several components at once, names can be exchanged between components.
+    ///
+    /// All names are checked before renaming: invalid names, components renamed twice and
+    /// components sharing the same name after renaming are reported together, and leave the
+    /// names unchanged. Returns the number of renamed components.
+    pub fn set_names(&mut self, names: &[(usize, String)]) -> Result<usize, GenericError> {
+        self.check_names(names)?;
+
+        // Release all old names before assigning the new ones
+        let renamed: Vec<(usize, String)> = names
+            .iter()
+            .filter(|(c, n)| self.get_name(*c) != n)
+            .cloned()
+            .collect();
+        for (cid, _) in renamed.iter() {
+            self.unregister_names(*cid);
+        }
+        for (cid, name) in renamed.iter() {
+            self.register_names(*cid, name);
+        }
+        if !renamed.is_empty() {
+            self.changed();
+        }
+        Ok(renamed.len())
+    }
+
+    /// Check that several components can be renamed at once, see set_names
+    pub fn check_names(&self, names: &[(usize, String)]) -> CanFail<GenericError> {
+        let mut errors = vec![];
+        let mut renamed: HashMap<usize, &str> = HashMap::new();
+        for (cid, name) in names {
+            if !self.cpt_to_variables.contains_key(cid) {
+                errors.push(format!("unknown component {}", cid));
+                continue;
+            }
+            if !RE_UID.is_match(name) {
+                errors.push(format!("invalid name {}", name));
+            }
+            match renamed.insert(*cid, name) {
+                Some(other) if other != name => errors.push(format!(
+                    "{} renamed to both {} and {}",
+                    self.get_name(*cid),
+                    other,
+                    name
+                )),
+                _ => (),
+            }
+        }
+
+        // Detect components sharing a name after renaming
+        let mut owners: HashMap<&str, Vec<usize>> = HashMap::new();
+        for cid in self.components.iter() {
+            let name = renamed
+                .get(cid)
+                .copied()
+                .unwrap_or_else(|| self.get_name(*cid));
+            owners.entry(name).or_default().push(*cid);
+        }
+        let mut collisions: Vec<String> = owners
+            .into_iter()
+            .filter(|(_, owners)| owners.len() > 1)
+            .map(|(name, owners)| {
+                let sources: Vec<&str> = owners.iter().map(|c| self.get_name(*c)).collect();
+                format!("{} would be shared by {}", name, sources.join(", "))
+            })
+            .collect();
+        collisions.sort();
+        errors.extend(collisions);
+        if !errors.is_empty() {
+            return Err(GenericError::new(format!(
+                "Can not rename: {}",
+                errors.join("; ")
+            )));
+        }
+        Ok(())
+    }
+
+    fn unregister_names(&mut self, cid: usize) {
+        for v in self.cpt_to_variables.get(&cid).unwrap() {
+            if let Some(name) = self.names.get(v) {
+                self.name2uid.remove(name);
+            }
+        }
+    }
+
+    /// Name a component and all its threshold variables
+    fn register_names(&mut self, cid: usize, name: &str) {
+        for (i, v) in self.cpt_to_variables.get(&cid).unwrap().iter().enumerate() {
+            let newname = if i == 0 {
+                String::from(name)
+            } else {
+                format!("{}:{}", name, i + 1)
+            };
+            self.name2uid.insert(newname.clone(), *v);
+            self.names.insert(*v, newname);
+        }
+    }
+
     /// Make sure that a handle exists
     fn ensure_handle(&mut self, handle: usize) {
-        if self.var_to_cpt_value.get(&handle).is_some() {
+        if self.var_to_cpt_value.contains_key(&handle) {
             return;
         }
 
@@ -204,10 +522,14 @@ impl ModelVariables {
 
 impl GroupedVariables for ModelVariables {
     fn get_handle(&self, name: &str) -> Option<usize> {
-        if let Some(h) = self.name2uid.get(name) {
-            return Some(*h);
+        if let Some(h) = self.find_identifier(name) {
+            return Some(h);
         }
-        None
+        // Fallback to the labels which do not match any identifier
+        self.components
+            .iter()
+            .find(|cid| self.labels.get(cid).map(String::as_str) == Some(name))
+            .copied()
     }
 
     fn get_component_value(&self, vid: usize) -> Option<&Variable> {
@@ -215,7 +537,7 @@ impl GroupedVariables for ModelVariables {
     }
 
     fn get_name(&self, handle: usize) -> &str {
-        &self.names.get(&handle).unwrap_or(&EMPTY_NAME)
+        self.names.get(&handle).unwrap_or(&EMPTY_NAME)
     }
 
     fn get_variables(&self, handle: usize) -> &Vec<usize> {
@@ -223,69 +545,99 @@ impl GroupedVariables for ModelVariables {
         self.cpt_to_variables.get(&cpt).unwrap_or(&EMPTY_USIZE_VEC)
     }
 
-    fn components(&self) -> Iter<usize> {
+    fn components(&self) -> Iter<'_, usize> {
         self.components.iter()
     }
 
-    fn variables(&self) -> Iter<usize> {
+    fn variables(&self) -> Iter<'_, usize> {
         self.variables.iter()
     }
 
-    fn ensure(&mut self, name: &str) -> usize {
-        if let Some(uid) = self.get_handle(name) {
-            return uid;
+    fn try_ensure(&mut self, name: &str) -> Result<usize, GenericError> {
+        if let Some(uid) = self.find_identifier(name) {
+            return Ok(uid);
         }
 
-        let cap = match RE_VAR_ID.captures(&name) {
-            None => panic!("Invalid name: {}", name),
-            Some(c) => c,
+        // Split and check the threshold suffix before creating anything
+        let (cpt_name, value) = match RE_THRESHOLD.captures(name) {
+            None => (name, None),
+            Some(cap) => match cap.name("th").unwrap().as_str().parse::<usize>() {
+                Ok(v) if (1..=MAX_LEVEL).contains(&v) => {
+                    (cap.name("cpt").unwrap().as_str(), Some(v))
+                }
+                _ => {
+                    return Err(GenericError::new(format!(
+                        "Invalid threshold in {}: levels range from 1 to {}",
+                        name, MAX_LEVEL
+                    )))
+                }
+            },
         };
 
         // Retrieve or create the component
-        let cpt_name = cap.name("cpt").unwrap().as_str();
-        let cid = match self.get_handle(cpt_name) {
+        let cid = match self.find_identifier(cpt_name) {
             Some(c) => c,
             None => {
-                // Create a new component
                 let cid = self._next_handle;
-                self._create_component(self._next_handle, cpt_name);
+                if RE_UID.is_match(cpt_name) {
+                    self._create_component(cid, cpt_name);
+                } else {
+                    let normalized = self.normalize(cpt_name);
+                    self._create_component(cid, &normalized);
+                    self.labels.insert(cid, cpt_name.to_owned());
+                    self.original2uid.insert(cpt_name.to_owned(), cid);
+                }
                 cid
             }
         };
 
-        match cap.name("th") {
-            None => cid,
-            Some(t) => self.ensure_threshold(cid, t.as_str().parse().unwrap()),
+        match value {
+            None => Ok(cid),
+            Some(v) => self.ensure_threshold(cid, v),
         }
     }
 
-    fn ensure_threshold(&mut self, vid: usize, value: usize) -> usize {
-        let value = check_val(value);
+    fn ensure_threshold(&mut self, vid: usize, value: usize) -> Result<usize, GenericError> {
+        if !(1..=MAX_LEVEL).contains(&value) {
+            return Err(GenericError::new(format!(
+                "Invalid threshold {}: levels range from 1 to {}",
+                value, MAX_LEVEL
+            )));
+        }
         self.ensure_handle(vid);
         let cid = self.component(vid).unwrap();
         let variables = self.cpt_to_variables.get_mut(&cid).unwrap();
         let cptname = self.names.get(&cid).unwrap().to_string();
 
-        // Create new variable(s) as required
+        // Create new variable(s) as required, next to the existing variables of the component
         let mut changed = false;
-        for v in variables.len()..value {
+        let order = &self.variables;
+        let first = variables
+            .last()
+            .and_then(|last| order.iter().position(|v| v == last))
+            .map(|p| p + 1)
+            .unwrap_or(order.len());
+        for (position, v) in (first..).zip(variables.len()..value) {
             let vid = self._next_handle;
             self._next_handle += 1;
             changed = true;
-            self.names.insert(vid, format!("{}:{}", cptname, v + 1));
+            let name = format!("{}:{}", cptname, v + 1);
+            self.name2uid.insert(name.clone(), vid);
+            self.names.insert(vid, name);
             self.var_to_cpt_value.insert(vid, Variable::new(cid, v + 1));
+            self.variables.insert(position, vid);
             variables.push(vid);
         }
         if changed {
             self.changed();
         }
         // Return the variable
-        self.cpt_to_variables.get(&cid).unwrap()[value - 1]
+        Ok(self.cpt_to_variables.get(&cid).unwrap()[value - 1])
     }
 
     fn set_name(&mut self, h: usize, name: &str) -> Result<bool, &'static str> {
         // Reject invalid new names
-        if !RE_UID.is_match(&name) {
+        if !RE_UID.is_match(name) {
             return Err("Invalid name");
         }
 
@@ -301,19 +653,9 @@ impl GroupedVariables for ModelVariables {
             };
         }
 
-        // Update the names of all associated variables
-        let variables = self.cpt_to_variables.get(&ch).unwrap();
-        for (i, v) in variables.iter().enumerate() {
-            self.name2uid.remove(self.names.get(v).unwrap());
-            let newname = if *v == 0 {
-                String::from(name)
-            } else {
-                format!("{}:{}", name, i)
-            };
-            self.name2uid.insert(newname.clone(), *v);
-            self.names.insert(*v, newname);
-        }
-
+        self.unregister_names(ch);
+        self.register_names(ch, name);
+        self.changed();
         Ok(true)
     }
 }
@@ -342,24 +684,3 @@ where
         self
     }
 }
-
-pub fn check_val(value: usize) -> usize {
-    if value < 1 {
-        eprintln!("Tried to access an impossible value: {}", value);
-        return 1;
-    }
-
-    if value > MAXVAL {
-        eprintln!("Tried to access a large value: {}", value);
-        return MAXVAL;
-    }
-    value
-}
-
-pub fn check_tval(value: usize) -> usize {
-    if value > MAXVAL {
-        eprintln!("Tried to access a large value: {}", value);
-        return MAXVAL;
-    }
-    value
-}